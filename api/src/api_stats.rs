@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+/// The statistics of the calls to the [SwitchBot API], by endpoints.
+///
+/// The endpoints are the last segments of the URLs,
+/// such as `devices`, `status`, and `commands`.
+/// Please see [`SwitchBot::api_stats()`][crate::SwitchBot::api_stats()].
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::SwitchBot;
+/// # fn test(switch_bot: &SwitchBot) {
+/// for (endpoint, stats) in switch_bot.api_stats().endpoints() {
+///     println!("{endpoint}: {} calls, {:?}", stats.count(), stats.total_latency());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ApiStats {
+    endpoints: Mutex<BTreeMap<String, EndpointStats>>,
+}
+
+/// The statistics of an endpoint in [`ApiStats`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointStats {
+    count: u64,
+    errors: u64,
+    total_latency: Duration,
+}
+
+impl EndpointStats {
+    /// The number of the calls.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The number of the calls that failed.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// The sum of the latencies of all calls.
+    pub fn total_latency(&self) -> Duration {
+        self.total_latency
+    }
+}
+
+impl ApiStats {
+    /// The statistics of each endpoint, in the alphabetical order.
+    pub fn endpoints(&self) -> Vec<(String, EndpointStats)> {
+        let endpoints = self.endpoints.lock().unwrap();
+        endpoints
+            .iter()
+            .map(|(endpoint, stats)| (endpoint.clone(), stats.clone()))
+            .collect()
+    }
+
+    /// The number of the calls of all endpoints.
+    pub fn total_count(&self) -> u64 {
+        let endpoints = self.endpoints.lock().unwrap();
+        endpoints.values().map(|stats| stats.count).sum()
+    }
+
+    pub(crate) fn record(&self, url: &str, latency: Duration, is_ok: bool) {
+        let endpoint = Self::endpoint(url);
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let stats = endpoints.entry(endpoint.into()).or_default();
+        stats.count += 1;
+        if !is_ok {
+            stats.errors += 1;
+        }
+        stats.total_latency += latency;
+    }
+
+    fn endpoint(url: &str) -> &str {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        path.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let stats = ApiStats::default();
+        let host = "https://api.switch-bot.com/v1.1";
+        stats.record(&format!("{host}/devices"), Duration::from_millis(100), true);
+        stats.record(
+            &format!("{host}/devices/D1/status"),
+            Duration::from_millis(50),
+            true,
+        );
+        stats.record(
+            &format!("{host}/devices/D2/status"),
+            Duration::from_millis(70),
+            false,
+        );
+        assert_eq!(stats.total_count(), 3);
+        let endpoints = stats.endpoints();
+        let names: Vec<&str> = endpoints.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["devices", "status"]);
+        let status = &endpoints[1].1;
+        assert_eq!(status.count(), 2);
+        assert_eq!(status.errors(), 1);
+        assert_eq!(status.total_latency(), Duration::from_millis(120));
+    }
+}
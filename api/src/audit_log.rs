@@ -0,0 +1,374 @@
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    str::FromStr,
+    sync::{Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use crate::{CommandRequest, CommandResponse, Device};
+
+/// Where a command came from, recorded in the [`AuditLog`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommandSource {
+    /// Entered by a user interactively.
+    Interactive,
+    /// Given in the command line arguments.
+    Batch,
+    /// Read from a script, such as a redirected standard input.
+    Script,
+    /// Sent by programs. This is the default.
+    #[default]
+    Automation,
+}
+
+impl CommandSource {
+    const NAMES: [(CommandSource, &str); 4] = [
+        (CommandSource::Interactive, "interactive"),
+        (CommandSource::Batch, "batch"),
+        (CommandSource::Script, "script"),
+        (CommandSource::Automation, "automation"),
+    ];
+}
+
+impl Display for CommandSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, name) = Self::NAMES.iter().find(|(s, _)| s == self).unwrap();
+        f.write_str(name)
+    }
+}
+
+impl FromStr for CommandSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|(source, _)| *source)
+            .ok_or_else(|| anyhow::anyhow!(r#"Unknown command source "{s}""#))
+    }
+}
+
+/// A record of a command in the [`AuditLog`].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    timestamp: u64,
+    device_id: String,
+    device_name: String,
+    command: CommandRequest,
+    source: CommandSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    latency_ms: u64,
+}
+
+impl AuditRecord {
+    pub(crate) fn new(
+        device: &Device,
+        command: &CommandRequest,
+        source: CommandSource,
+        result: &anyhow::Result<CommandResponse>,
+        latency: Duration,
+    ) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            device_id: device.device_id().into(),
+            device_name: device.device_name().into(),
+            // Not to store secrets such as the password of `createKey`.
+            command: command.redacted(),
+            source,
+            error: result.as_ref().err().map(|error| format!("{error:#}")),
+            latency_ms: latency.as_millis().try_into().unwrap_or(u64::MAX),
+        }
+    }
+
+    /// The time the command was sent.
+    pub fn timestamp(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.timestamp)
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    pub fn command(&self) -> &CommandRequest {
+        &self.command
+    }
+
+    pub fn source(&self) -> CommandSource {
+        self.source
+    }
+
+    /// The error message if the command failed.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The time the [SwitchBot API] took to respond.
+    ///
+    /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+    pub fn latency(&self) -> Duration {
+        Duration::from_millis(self.latency_ms)
+    }
+}
+
+impl Display for AuditRecord {
+    /// Write the record in a line, with the timestamp in UTC.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}) {} [{}] {}ms ",
+            format_utc(self.timestamp),
+            self.device_name,
+            self.device_id,
+            self.command,
+            self.source,
+            self.latency_ms
+        )?;
+        match &self.error {
+            Some(error) => write!(f, "ERROR: {error}"),
+            None => write!(f, "OK"),
+        }
+    }
+}
+
+/// Format the unix seconds as `YYYY-MM-DDThh:mm:ssZ`.
+fn format_utc(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    // The civil-from-days algorithm by Howard Hinnant.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// An append-only log of the commands sent to devices.
+///
+/// Each command sent by [`Device::command()`] is written to the log
+/// as a line of JSON ([JSON Lines]) of an [`AuditRecord`].
+/// Please see [`SwitchBot::set_audit_log()`][crate::SwitchBot::set_audit_log()].
+///
+/// [JSON Lines]: https://jsonlines.org/
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{AuditLog, SwitchBot};
+/// # fn test(switch_bot: &mut SwitchBot) -> anyhow::Result<()> {
+/// switch_bot.set_audit_log(AuditLog::open("audit.jsonl")?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AuditLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+    source: RwLock<CommandSource>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("source", &self.source())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    /// Construct an instance that writes to the `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+            source: RwLock::default(),
+        }
+    }
+
+    /// Construct an instance that appends to the file.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+
+    /// Read the [`AuditRecord`]s from the file written by [`AuditLog::open()`].
+    /// Empty if the file doesn't exist.
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Vec<AuditRecord>> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut records = Vec::new();
+        for line in io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(error) => log::warn!("Skipped an invalid audit record: {error}"),
+            }
+        }
+        Ok(records)
+    }
+
+    /// The [`CommandSource`] to record for the following commands.
+    pub fn source(&self) -> CommandSource {
+        *self.source.read().unwrap()
+    }
+
+    /// Set the [`CommandSource`] to record for the following commands.
+    pub fn set_source(&self, source: CommandSource) {
+        *self.source.write().unwrap() = source;
+    }
+
+    pub(crate) fn record(
+        &self,
+        device: &Device,
+        command: &CommandRequest,
+        result: &anyhow::Result<CommandResponse>,
+        latency: Duration,
+    ) {
+        let record = AuditRecord::new(device, command, self.source(), result, latency);
+        if let Err(error) = self.write(&record) {
+            log::error!("Failed to write the audit log: {error}");
+        }
+    }
+
+    fn write(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(line.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A [`Write`] that can be read after moved to the [`AuditLog`].
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record() -> anyhow::Result<()> {
+        let buffer = SharedBuffer::default();
+        let log = AuditLog::new(buffer.clone());
+        log.set_source(CommandSource::Batch);
+        let device = Device::new_for_test(1);
+        let command = CommandRequest::from("turnOn");
+        log.record(
+            &device,
+            &command,
+            &Ok(CommandResponse::default()),
+            Duration::from_millis(120),
+        );
+        log.record(
+            &device,
+            &command,
+            &Err(anyhow::anyhow!("offline")),
+            Duration::ZERO,
+        );
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone())?;
+        let records: Vec<AuditRecord> = text
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].device_id(), "device1");
+        assert_eq!(records[0].command(), &command);
+        assert_eq!(records[0].source(), CommandSource::Batch);
+        assert_eq!(records[0].latency(), Duration::from_millis(120));
+        assert!(records[0].is_success());
+        assert_eq!(records[1].error(), Some("offline"));
+        Ok(())
+    }
+
+    #[test]
+    fn record_redacted() -> anyhow::Result<()> {
+        let buffer = SharedBuffer::default();
+        let log = AuditLog::new(buffer.clone());
+        let command = crate::Passcode::permanent("me", "918273").to_command()?;
+        log.record(
+            &Device::new_for_test(1),
+            &command,
+            &Ok(CommandResponse::default()),
+            Duration::ZERO,
+        );
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone())?;
+        assert!(text.contains("createKey"), "{text}");
+        assert!(!text.contains("918273"), "{text}");
+        let record: AuditRecord = serde_json::from_str(text.trim_end())?;
+        assert_eq!(
+            serde_json::to_value(record.command())?["parameter"]["password"],
+            crate::SecretString::REDACTED
+        );
+        assert!(!record.to_string().contains("918273"));
+        Ok(())
+    }
+
+    #[test]
+    fn format_utc() {
+        assert_eq!(super::format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(super::format_utc(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(super::format_utc(1_792_292_400), "2026-10-18T03:00:00Z");
+    }
+
+    #[test]
+    fn command_source() -> anyhow::Result<()> {
+        assert_eq!(CommandSource::Script.to_string(), "script");
+        assert_eq!(
+            "Interactive".parse::<CommandSource>()?,
+            CommandSource::Interactive
+        );
+        assert!("unknown".parse::<CommandSource>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+/// The per-device results of [`SwitchBot::command_many()`],
+/// [`SwitchBot::command_many_if_needed()`], and [`SwitchBot::update_status_many()`].
+///
+/// The results are in the same order as the device IDs given to the functions.
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{CommandRequest, SwitchBot};
+/// # async fn test(switch_bot: &SwitchBot) -> anyhow::Result<()> {
+/// let command = CommandRequest::from("turnOff");
+/// let report = switch_bot.command_many(&["id1", "id2"], &command).await;
+/// for (device_id, result) in report.iter() {
+///     if let Err(error) = result {
+///         println!("{device_id}: {error}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct BatchReport<T = ()> {
+    results: Vec<(String, anyhow::Result<T>)>,
+}
+
+impl<T> BatchReport<T> {
+    pub(crate) fn new(results: Vec<(String, anyhow::Result<T>)>) -> Self {
+        Self { results }
+    }
+
+    /// The number of the devices.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// True if there are no devices.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns an iterator of the device IDs and their results.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &anyhow::Result<T>)> {
+        self.results
+            .iter()
+            .map(|(device_id, result)| (device_id.as_str(), result))
+    }
+
+    /// The number of the devices that succeeded.
+    pub fn num_succeeded(&self) -> usize {
+        self.results.iter().filter(|(_, r)| r.is_ok()).count()
+    }
+
+    /// The number of the devices that failed.
+    pub fn num_failed(&self) -> usize {
+        self.len() - self.num_succeeded()
+    }
+
+    /// True if all devices succeeded.
+    pub fn is_all_succeeded(&self) -> bool {
+        self.results.iter().all(|(_, r)| r.is_ok())
+    }
+
+    /// Returns an iterator of the device IDs and the errors of the failed devices.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &anyhow::Error)> {
+        self.results.iter().filter_map(|(device_id, result)| {
+            result.as_ref().err().map(|e| (device_id.as_str(), e))
+        })
+    }
+
+    /// Convert the successful results by the function `f`.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> BatchReport<U> {
+        BatchReport::new(
+            self.results
+                .into_iter()
+                .map(|(device_id, result)| (device_id, result.map(&mut f)))
+                .collect(),
+        )
+    }
+}
+
+/// Collect the pairs of the device ID and its result,
+/// for callers that run per-device operations by themselves.
+impl<T> FromIterator<(String, anyhow::Result<T>)> for BatchReport<T> {
+    fn from_iter<I: IntoIterator<Item = (String, anyhow::Result<T>)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for BatchReport<T> {
+    type Item = (String, anyhow::Result<T>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let report = BatchReport::new(vec![
+            ("a".into(), Ok(())),
+            ("b".into(), Err(anyhow::anyhow!("error"))),
+            ("c".into(), Ok(())),
+        ]);
+        assert_eq!(report.len(), 3);
+        assert_eq!(report.num_succeeded(), 2);
+        assert_eq!(report.num_failed(), 1);
+        assert!(!report.is_all_succeeded());
+        let failures: Vec<&str> = report.failures().map(|(id, _)| id).collect();
+        assert_eq!(failures, ["b"]);
+    }
+
+    #[test]
+    fn map() {
+        let report = BatchReport::new(vec![
+            ("a".into(), Ok(1)),
+            ("b".into(), Err(anyhow::anyhow!("error"))),
+        ]);
+        let report = report.map(|value| value * 2);
+        let values: Vec<Option<i32>> = report
+            .iter()
+            .map(|(_, r)| r.as_ref().ok().copied())
+            .collect();
+        assert_eq!(values, [Some(2), None]);
+    }
+}
@@ -0,0 +1,418 @@
+//! A blocking API, for programs that don't use an async runtime.
+//!
+//! The types in this module mirror the async API with synchronous methods,
+//! similar to [`reqwest::blocking`].
+//! They drive the async implementation by an internal [`tokio`] runtime,
+//! so they must not be used from within an async runtime.
+//!
+//! This module requires the `blocking` feature.
+//!
+//! # Examples
+//! ```no_run
+//! # use switchbot_api::{blocking::SwitchBot, CommandRequest};
+//! # fn test(token: &str, secret: &str) -> anyhow::Result<()> {
+//! let mut switch_bot = SwitchBot::new_with_authentication(token, secret);
+//! switch_bot.load_devices()?;
+//! let device = switch_bot.device(0).unwrap();
+//! let command = CommandRequest {
+//!     command: "turnOn".into(),
+//!     ..Default::default()
+//! };
+//! device.command(&command)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fmt::Display,
+    ops::{Deref, DerefMut},
+    path::Path,
+    time::Duration,
+};
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    ApiStats, AuditLog, BatchReport, Cassette, CommandRequest, CommandResponse, Condition,
+    CredentialProvider, KeypadRequest, Passcode, ShadowState, Transport,
+};
+
+fn new_runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create a tokio runtime")
+}
+
+/// The blocking version of [`crate::SwitchBot`].
+#[derive(Debug)]
+pub struct SwitchBot {
+    inner: crate::SwitchBot,
+    runtime: Runtime,
+}
+
+impl Default for SwitchBot {
+    fn default() -> Self {
+        Self::from_async(crate::SwitchBot::default())
+    }
+}
+
+impl SwitchBot {
+    fn from_async(inner: crate::SwitchBot) -> Self {
+        Self {
+            inner,
+            runtime: new_runtime(),
+        }
+    }
+
+    /// Please see [`crate::SwitchBot::new()`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Please see [`crate::SwitchBot::new_with_authentication()`].
+    pub fn new_with_authentication(token: &str, secret: &str) -> Self {
+        Self::from_async(crate::SwitchBot::new_with_authentication(token, secret))
+    }
+
+    /// Please see [`crate::SwitchBot::new_with_credential_provider()`].
+    pub fn new_with_credential_provider(provider: &dyn CredentialProvider) -> anyhow::Result<Self> {
+        Ok(Self::from_async(
+            crate::SwitchBot::new_with_credential_provider(provider)?,
+        ))
+    }
+
+    /// Please see [`crate::SwitchBot::new_for_test_with_transport()`].
+    pub fn new_for_test_with_transport(transport: impl Transport + 'static) -> Self {
+        Self::from_async(crate::SwitchBot::new_for_test_with_transport(transport))
+    }
+
+    /// The underlying async [`crate::SwitchBot`].
+    pub fn as_async(&self) -> &crate::SwitchBot {
+        &self.inner
+    }
+
+    /// Please see [`crate::SwitchBot::set_authentication()`].
+    pub fn set_authentication(&mut self, token: &str, secret: &str) {
+        self.inner.set_authentication(token, secret);
+    }
+
+    /// Please see [`crate::SwitchBot::add_account()`].
+    pub fn add_account(&mut self, name: &str, token: &str, secret: &str) {
+        self.inner.add_account(name, token, secret);
+    }
+
+    /// Please see [`crate::SwitchBot::account_names()`].
+    pub fn account_names(&self) -> impl Iterator<Item = &str> {
+        self.inner.account_names()
+    }
+
+    /// Please see [`crate::SwitchBot::set_transport()`].
+    pub fn set_transport(&mut self, transport: impl Transport + 'static) {
+        self.inner.set_transport(transport);
+    }
+
+    /// Please see [`crate::SwitchBot::set_cassette()`].
+    pub fn set_cassette(&mut self, cassette: Cassette) {
+        self.inner.set_cassette(cassette);
+    }
+
+    /// Please see [`crate::SwitchBot::set_shadow_state()`].
+    pub fn set_shadow_state(&mut self, state: ShadowState) {
+        self.inner.set_shadow_state(state);
+    }
+
+    /// Please see [`crate::SwitchBot::shadow_state()`].
+    pub fn shadow_state(&self) -> Option<ShadowState> {
+        self.inner.shadow_state()
+    }
+
+    /// Please see [`crate::SwitchBot::set_audit_log()`].
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        self.inner.set_audit_log(audit_log);
+    }
+
+    /// Please see [`crate::SwitchBot::audit_log()`].
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.inner.audit_log()
+    }
+
+    /// Please see [`crate::SwitchBot::api_stats()`].
+    pub fn api_stats(&self) -> &ApiStats {
+        self.inner.api_stats()
+    }
+
+    /// The number of [`Device`]s.
+    pub fn num_devices(&self) -> usize {
+        self.inner.devices().len()
+    }
+
+    /// The [`Device`] at the `index`.
+    pub fn device(&self, index: usize) -> Option<Device<'_>> {
+        self.inner
+            .devices()
+            .get(index)
+            .map(|device| Device::new(device, &self.runtime))
+    }
+
+    /// Returns an iterator of [`Device`]s.
+    /// Please see [`crate::SwitchBot::devices()`].
+    pub fn devices(&self) -> impl ExactSizeIterator<Item = Device<'_>> {
+        self.inner
+            .devices()
+            .iter()
+            .map(|device| Device::new(device, &self.runtime))
+    }
+
+    /// Please see [`crate::SwitchBot::load_devices()`].
+    pub fn load_devices(&mut self) -> anyhow::Result<()> {
+        self.runtime.block_on(self.inner.load_devices())
+    }
+
+    /// Please see [`crate::SwitchBot::command_many()`].
+    pub fn command_many(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+    ) -> BatchReport<CommandResponse> {
+        self.runtime
+            .block_on(self.inner.command_many(device_ids, command))
+    }
+
+    /// Please see [`crate::SwitchBot::command_many_if_needed()`].
+    pub fn command_many_if_needed(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+    ) -> BatchReport<Option<CommandResponse>> {
+        self.runtime
+            .block_on(self.inner.command_many_if_needed(device_ids, command))
+    }
+
+    /// Please see [`crate::SwitchBot::command_many_and_wait()`].
+    pub fn command_many_and_wait(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+        timeout: Duration,
+    ) -> BatchReport<CommandResponse> {
+        self.runtime.block_on(
+            self.inner
+                .command_many_and_wait(device_ids, command, timeout),
+        )
+    }
+
+    /// Please see [`crate::SwitchBot::update_status_many()`].
+    pub fn update_status_many(&self, device_ids: &[&str]) -> BatchReport {
+        self.runtime
+            .block_on(self.inner.update_status_many(device_ids))
+    }
+}
+
+/// The blocking version of [`crate::Device`].
+///
+/// The non-async functions of [`crate::Device`] are available by [`Deref`].
+#[derive(Clone, Copy, Debug)]
+pub struct Device<'a> {
+    inner: &'a crate::Device,
+    runtime: &'a Runtime,
+}
+
+impl<'a> Device<'a> {
+    fn new(inner: &'a crate::Device, runtime: &'a Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Please see [`crate::Device::command()`].
+    pub fn command(&self, command: &CommandRequest) -> anyhow::Result<CommandResponse> {
+        self.runtime.block_on(self.inner.command(command))
+    }
+
+    /// Please see [`crate::Device::command_if_needed()`].
+    pub fn command_if_needed(
+        &self,
+        command: &CommandRequest,
+    ) -> anyhow::Result<Option<CommandResponse>> {
+        self.runtime.block_on(self.inner.command_if_needed(command))
+    }
+
+    /// Please see [`crate::Device::command_and_wait()`].
+    pub fn command_and_wait(
+        &self,
+        command: &CommandRequest,
+        expected: &Condition,
+        timeout: Duration,
+    ) -> anyhow::Result<CommandResponse> {
+        self.runtime
+            .block_on(self.inner.command_and_wait(command, expected, timeout))
+    }
+
+    /// Please see [`crate::Device::update_status()`].
+    pub fn update_status(&self) -> anyhow::Result<()> {
+        self.runtime.block_on(self.inner.update_status())
+    }
+}
+
+impl Deref for Device<'_> {
+    type Target = crate::Device;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl Display for Device<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// The blocking version of [`crate::Keypad`].
+///
+/// The non-async functions of [`crate::Keypad`] are available by [`Deref`].
+#[derive(Debug)]
+pub struct Keypad<'a> {
+    inner: crate::Keypad<'a>,
+    runtime: &'a Runtime,
+}
+
+impl<'a> Keypad<'a> {
+    /// Please see [`crate::Keypad::new()`].
+    pub fn new(device: Device<'a>) -> anyhow::Result<Self> {
+        Ok(Self {
+            inner: crate::Keypad::new(device.inner)?,
+            runtime: device.runtime,
+        })
+    }
+
+    /// Please see [`crate::Keypad::create_key()`].
+    pub fn create_key(&self, passcode: &Passcode) -> anyhow::Result<KeypadRequest> {
+        self.runtime.block_on(self.inner.create_key(passcode))
+    }
+
+    /// Please see [`crate::Keypad::delete_key()`].
+    pub fn delete_key(&self, id: u64) -> anyhow::Result<KeypadRequest> {
+        self.runtime.block_on(self.inner.delete_key(id))
+    }
+}
+
+impl<'a> Deref for Keypad<'a> {
+    type Target = crate::Keypad<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// The blocking version of [`crate::Help`].
+///
+/// The non-async functions of [`crate::Help`] are available by [`Deref`].
+#[derive(Debug)]
+pub struct Help(crate::Help);
+
+impl Help {
+    /// Please see [`crate::Help::load()`].
+    pub fn load() -> anyhow::Result<Self> {
+        Ok(Self(crate::Help::load_built_in()?))
+    }
+
+    /// Please see [`crate::Help::load_from_path()`].
+    pub fn load_from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self(crate::Help::load_from_path(path)?))
+    }
+
+    /// Please see [`crate::Help::from_markdown_docs()`].
+    pub fn from_markdown_docs(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self(crate::Help::from_markdown_docs(dir)?))
+    }
+
+    /// The underlying [`crate::Help`].
+    pub fn into_inner(self) -> crate::Help {
+        self.0
+    }
+}
+
+impl Deref for Help {
+    type Target = crate::Help;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Help {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transport_with_devices() -> crate::ScriptedTransport {
+        crate::ScriptedTransport::default().device_list(&[
+            json!({"deviceId": "D1", "deviceName": "Bot", "deviceType": "Bot", "hubDeviceId": ""}),
+            json!({"deviceId": "D2", "deviceName": "Door", "deviceType": "Keypad", "hubDeviceId": ""}),
+        ])
+    }
+
+    fn power_on() -> serde_json::Value {
+        json!({"deviceId": "D1", "hubDeviceId": "", "power": "on"})
+    }
+
+    #[test]
+    fn switch_bot() -> anyhow::Result<()> {
+        let transport = transport_with_devices().ok(json!({})).ok(power_on());
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices()?;
+        assert_eq!(switch_bot.num_devices(), 2);
+        let device = switch_bot.device(0).unwrap();
+        assert_eq!(device.device_id(), "D1");
+        device.command(&CommandRequest {
+            command: "turnOn".into(),
+            ..Default::default()
+        })?;
+        device.update_status()?;
+        assert_eq!(device.status_by_key("power"), Some("on".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn command_many() -> anyhow::Result<()> {
+        let transport = transport_with_devices().ok(json!({})).ok(power_on());
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices()?;
+        let turn_on = CommandRequest::from("turnOn");
+        let report = switch_bot.command_many(&["D1", "X"], &turn_on);
+        assert_eq!(report.num_succeeded(), 1);
+        assert_eq!(
+            report.failures().map(|(id, _)| id).collect::<Vec<_>>(),
+            ["X"]
+        );
+        assert!(switch_bot.update_status_many(&["D1"]).is_all_succeeded());
+        // The power is already on.
+        let report = switch_bot.command_many_if_needed(&["D1"], &turn_on);
+        assert!(matches!(report.iter().next(), Some((_, Ok(None)))));
+        Ok(())
+    }
+
+    #[test]
+    fn keypad() -> anyhow::Result<()> {
+        let transport = transport_with_devices().ok(json!({}));
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices()?;
+        assert!(Keypad::new(switch_bot.device(0).unwrap()).is_err());
+        let keypad = Keypad::new(switch_bot.device(1).unwrap())?;
+        let request = keypad.create_key(&Passcode::permanent("guest", "123456"))?;
+        assert_eq!(request.device_id(), "D2");
+        Ok(())
+    }
+
+    #[test]
+    fn help() -> anyhow::Result<()> {
+        let help = Help::load()?;
+        assert!(help.num_device_types() > 0);
+        Ok(())
+    }
+}
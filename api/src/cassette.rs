@@ -0,0 +1,210 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::*;
+
+/// Records the requests to the [SwitchBot API] and their responses,
+/// or replays the recorded responses without the network access.
+///
+/// This is useful for deterministic tests and for bug reports.
+/// The authentication headers are not recorded.
+///
+/// Please see [`SwitchBot::set_cassette()`][crate::SwitchBot::set_cassette()].
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{Cassette, SwitchBot};
+/// # async fn test() -> anyhow::Result<()> {
+/// let mut switch_bot = SwitchBot::new();
+/// switch_bot.set_cassette(Cassette::replay("cassette.json")?);
+/// switch_bot.load_devices().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Cassette {
+    is_replay: bool,
+    path: Option<PathBuf>,
+    state: Mutex<CassetteState>,
+}
+
+#[derive(Debug, Default)]
+struct CassetteState {
+    interactions: Vec<Interaction>,
+    is_used: Vec<bool>,
+}
+
+/// A pair of a request and its response.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Interaction {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+    pub status: u16,
+    pub response: serde_json::Value,
+}
+
+impl Interaction {
+    /// Create from the method, the URL, and the body of the `request`,
+    /// and the HTTP `status` and the `response`.
+    /// Headers are not included, and the body is redacted,
+    /// so that the credentials are not recorded.
+    pub fn new(request: &TransportRequest, status: u16, response: serde_json::Value) -> Self {
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            body: request.redacted_body(),
+            status,
+            response,
+        }
+    }
+}
+
+impl Cassette {
+    /// Construct an instance that records to the file at `path`.
+    /// The file is written on every response.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self {
+            is_replay: false,
+            path: Some(path.as_ref().to_path_buf()),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Construct an instance that replays the file recorded by [`Cassette::record()`].
+    pub fn replay(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)?;
+        Self::replay_from_json_str(&json)
+            .map_err(|error| anyhow::anyhow!("{}: {error}", path.display()))
+    }
+
+    /// Construct an instance that replays the JSON string
+    /// in the same format as the file [`Cassette::record()`] writes.
+    pub fn replay_from_json_str(json: &str) -> anyhow::Result<Self> {
+        let interactions: Vec<Interaction> = serde_json::from_str(json)?;
+        Ok(Self {
+            is_replay: true,
+            path: None,
+            state: Mutex::new(CassetteState {
+                is_used: vec![false; interactions.len()],
+                interactions,
+            }),
+        })
+    }
+
+    /// True if this instance replays, false if records.
+    pub fn is_replay(&self) -> bool {
+        self.is_replay
+    }
+
+    /// The number of the recorded interactions.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().interactions.len()
+    }
+
+    /// True if there are no recorded interactions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Find the response for the request.
+    ///
+    /// Interactions are matched by the method, the URL, and the redacted body.
+    /// If multiple interactions match, they are returned in the recorded order.
+    pub(crate) fn find(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> anyhow::Result<Interaction> {
+        let mut body = body.cloned();
+        if let Some(body) = &mut body {
+            SecretString::redact_json(body);
+        }
+        let body = body.as_ref();
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let index = state
+            .interactions
+            .iter()
+            .zip(&state.is_used)
+            .position(|(interaction, is_used)| {
+                !is_used
+                    && interaction.method == method
+                    && interaction.url == url
+                    && interaction.body.as_ref() == body
+            })
+            .ok_or_else(|| anyhow::anyhow!("No recorded response for {method} {url}"))?;
+        state.is_used[index] = true;
+        Ok(state.interactions[index].clone())
+    }
+
+    /// Add the interaction and save to the file.
+    pub(crate) fn push(&self, interaction: Interaction) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.interactions.push(interaction);
+        state.is_used.push(true);
+        if let Some(path) = &self.path {
+            let json = serde_json::to_string_pretty(&state.interactions)?;
+            fs::write(path, json)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn interaction(url: &str, response: serde_json::Value) -> Interaction {
+        Interaction {
+            method: "GET".into(),
+            url: url.into(),
+            body: None,
+            status: 200,
+            response,
+        }
+    }
+
+    #[test]
+    fn find() -> anyhow::Result<()> {
+        let json = serde_json::to_string(&[
+            interaction("a", json!(1)),
+            interaction("b", json!(2)),
+            interaction("a", json!(3)),
+        ])?;
+        let cassette = Cassette::replay_from_json_str(&json)?;
+        assert!(cassette.is_replay());
+        assert_eq!(cassette.len(), 3);
+        assert_eq!(cassette.find("GET", "b", None)?.response, json!(2));
+        assert_eq!(cassette.find("GET", "a", None)?.response, json!(1));
+        assert_eq!(cassette.find("GET", "a", None)?.response, json!(3));
+        assert!(cassette.find("GET", "a", None).is_err());
+        assert!(cassette.find("POST", "b", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn record() -> anyhow::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("switchbot-cassette-{}.json", uuid::Uuid::new_v4()));
+        let cassette = Cassette::record(&path);
+        assert!(!cassette.is_replay());
+        cassette.push(interaction("a", json!(1)))?;
+        cassette.push(interaction("b", json!(2)))?;
+
+        let replay = Cassette::replay(&path)?;
+        fs::remove_file(&path)?;
+        assert_eq!(replay.len(), 2);
+        assert_eq!(replay.find("GET", "b", None)?.response, json!(2));
+        Ok(())
+    }
+}
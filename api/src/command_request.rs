@@ -1,4 +1,6 @@
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+
+use crate::{Keypad, SecretString};
 
 /// A command request to send to the [SwitchBot API].
 ///
@@ -16,18 +18,21 @@ use std::fmt::Display;
 ///
 /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
 /// [send-device-control-commands]: https://github.com/OpenWonderLabs/SwitchBotAPI/blob/main/README.md#send-device-control-commands
-#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Default, PartialEq, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommandRequest {
     /// The command.
     pub command: String,
 
     /// The command parameters.
-    #[serde(skip_serializing_if = "CommandRequest::can_omit_parameter")]
+    ///
+    /// The parameters of the [`Keypad`][crate::Keypad] commands, such as `createKey`,
+    /// are sent as JSON objects rather than strings.
+    #[serde(default, deserialize_with = "CommandRequest::deserialize_parameter")]
     pub parameter: String,
 
     /// The command type.
-    #[serde(skip_serializing_if = "CommandRequest::can_omit_command_type")]
+    #[serde(default)]
     pub command_type: String,
 }
 
@@ -35,6 +40,36 @@ impl CommandRequest {
     const DEFAULT_PARAMETER: &str = "default";
     const DEFAULT_COMMAND_TYPE: &str = "command";
 
+    /// A copy with the secrets in the [`parameter`][CommandRequest::parameter]
+    /// replaced with [`SecretString::REDACTED`],
+    /// such as the `password` of the `createKey` command.
+    ///
+    /// [`Display`] and [`Debug`] are redacted too.
+    /// Serialization is not redacted, so that it can be sent.
+    /// ```
+    /// # use switchbot_api::CommandRequest;
+    /// let command = CommandRequest::from(r#"createKey:{"name":"me","password":"123456"}"#);
+    /// assert_eq!(
+    ///     command.redacted().parameter,
+    ///     r#"{"name":"me","password":"[REDACTED]"}"#
+    /// );
+    /// ```
+    pub fn redacted(&self) -> Self {
+        let mut command = self.clone();
+        if let Some(parameter) = Self::redacted_parameter(&self.parameter) {
+            command.parameter = parameter;
+        }
+        command
+    }
+
+    fn redacted_parameter(parameter: &str) -> Option<String> {
+        if !parameter.trim_start().starts_with('{') {
+            return None;
+        }
+        let mut object: serde_json::Value = serde_json::from_str(parameter).ok()?;
+        SecretString::redact_json(&mut object).then(|| object.to_string())
+    }
+
     fn can_omit_parameter(str: &str) -> bool {
         str.is_empty() || str == Self::DEFAULT_PARAMETER
     }
@@ -42,6 +77,54 @@ impl CommandRequest {
     fn can_omit_command_type(str: &str) -> bool {
         str.is_empty() || str == Self::DEFAULT_COMMAND_TYPE
     }
+
+    /// The [`parameter`][CommandRequest::parameter] as a JSON object,
+    /// if this command requires it.
+    fn object_parameter(&self) -> Option<serde_json::Value> {
+        if !Keypad::has_object_parameter(self) {
+            return None;
+        }
+        match serde_json::from_str(&self.parameter) {
+            Ok(object @ serde_json::Value::Object(_)) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Accept JSON objects, such as the parameters of the [`Keypad`][crate::Keypad] commands,
+    /// in addition to strings.
+    fn deserialize_parameter<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<String, D::Error> {
+        use serde::Deserialize;
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(parameter) => Ok(parameter),
+            object @ serde_json::Value::Object(_) => Ok(object.to_string()),
+            value => Err(serde::de::Error::custom(format!(
+                "The parameter must be a string or an object: {value}"
+            ))),
+        }
+    }
+}
+
+impl serde::Serialize for CommandRequest {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("CommandRequest", 3)?;
+        state.serialize_field("command", &self.command)?;
+        if Self::can_omit_parameter(&self.parameter) {
+            state.skip_field("parameter")?;
+        } else if let Some(object) = self.object_parameter() {
+            state.serialize_field("parameter", &object)?;
+        } else {
+            state.serialize_field("parameter", &self.parameter)?;
+        }
+        if Self::can_omit_command_type(&self.command_type) {
+            state.skip_field("commandType")?;
+        } else {
+            state.serialize_field("commandType", &self.command_type)?;
+        }
+        state.end()
+    }
 }
 
 impl Display for CommandRequest {
@@ -49,7 +132,8 @@ impl Display for CommandRequest {
     /// * Prepend `command_type` with a `/` (slash) if it's not empty nor default.
     /// * Append `parameter` with a `:` (colon) if it's not empty nor default.
     ///
-    /// This is the same form as what the [`from(&str)`][CommandRequest::from()] parses.
+    /// This is the same form as what the [`from(&str)`][CommandRequest::from()] parses,
+    /// except that secrets are [`redacted()`][CommandRequest::redacted()].
     ///
     /// [cli-command]: https://github.com/kojiishi/switchbot-rs/tree/main/cli#command
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -58,12 +142,26 @@ impl Display for CommandRequest {
         }
         write!(f, "{}", self.command)?;
         if !Self::can_omit_parameter(&self.parameter) {
-            write!(f, ":{}", self.parameter)?;
+            match Self::redacted_parameter(&self.parameter) {
+                Some(parameter) => write!(f, ":{parameter}")?,
+                None => write!(f, ":{}", self.parameter)?,
+            }
         }
         Ok(())
     }
 }
 
+impl Debug for CommandRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parameter = Self::redacted_parameter(&self.parameter);
+        f.debug_struct("CommandRequest")
+            .field("command", &self.command)
+            .field("parameter", parameter.as_ref().unwrap_or(&self.parameter))
+            .field("command_type", &self.command_type)
+            .finish()
+    }
+}
+
 impl From<&str> for CommandRequest {
     /// Parse a string into a [`CommandRequest`].
     /// Please see the [`switchbot-cli` document][cli-command] for the syntax.
@@ -186,6 +284,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_json_param() {
+        let with_param = CommandRequest {
+            command: "deleteKey".into(),
+            parameter: r#"{"id": 11}"#.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&with_param).unwrap(),
+            r#"{"command":"deleteKey","parameter":{"id":11}}"#
+        );
+        let deserialized: CommandRequest =
+            serde_json::from_str(r#"{"command":"deleteKey","parameter":{"id":11}}"#).unwrap();
+        assert_eq!(deserialized.parameter, r#"{"id":11}"#);
+        assert!(
+            serde_json::from_str::<CommandRequest>(r#"{"command":"a","parameter":1}"#).is_err()
+        );
+
+        // Other commands send JSON parameters as strings.
+        for (command, parameter) in [
+            ("setMode", r#"{"mode": 2, "fanGear": 1}"#),
+            (
+                "startClean",
+                r#"{"action":"sweep","param":{"fanLevel":1,"times":1}}"#,
+            ),
+        ] {
+            let request = CommandRequest {
+                command: command.into(),
+                parameter: parameter.into(),
+                ..Default::default()
+            };
+            assert_eq!(
+                serde_json::to_value(&request).unwrap()["parameter"],
+                serde_json::json!(parameter)
+            );
+        }
+
+        // Not a valid JSON.
+        let with_param = CommandRequest {
+            command: "test_command".into(),
+            parameter: "{param".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&with_param).unwrap(),
+            r#"{"command":"test_command","parameter":"{param"}"#
+        );
+    }
+
     #[test]
     fn serialize_type() {
         let with_type = CommandRequest {
@@ -0,0 +1,118 @@
+use std::fmt::Display;
+
+/// The response of [`Device::command()`][crate::Device::command()].
+///
+/// Most commands return no data,
+/// but some return the results of the operations,
+/// such as lock results or the acknowledgements of the keypad commands.
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{CommandRequest, Device};
+/// # async fn lock(device: &Device) -> anyhow::Result<()> {
+/// let response = device.command(&CommandRequest::from("lock")).await?;
+/// if let Some(body) = response.body() {
+///     println!("{body}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandResponse {
+    body: Option<serde_json::Value>,
+}
+
+impl CommandResponse {
+    pub(crate) fn new(body: Option<serde_json::Value>) -> Self {
+        // Treat `"body":{}` the same as no `body`.
+        let body = body.filter(|body| match body {
+            serde_json::Value::Null => false,
+            serde_json::Value::Object(object) => !object.is_empty(),
+            _ => true,
+        });
+        Self { body }
+    }
+
+    /// True if the response has no data.
+    pub fn is_empty(&self) -> bool {
+        self.body.is_none()
+    }
+
+    /// The `body` of the response, if it's not empty.
+    pub fn body(&self) -> Option<&serde_json::Value> {
+        self.body.as_ref()
+    }
+
+    /// Deserialize the `body` to the type `T`.
+    /// Returns `None` if the `body` is empty.
+    pub fn body_as<T: serde::de::DeserializeOwned>(&self) -> anyhow::Result<Option<T>> {
+        self.body
+            .as_ref()
+            .map(|body| serde_json::from_value(body.clone()))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// The ID of the command for async operations, if any.
+    pub fn command_id(&self) -> Option<&str> {
+        self.body
+            .as_ref()?
+            .get("commandId")
+            .and_then(serde_json::Value::as_str)
+    }
+
+    /// The results for each device, such as the lock results.
+    pub fn items(&self) -> &[serde_json::Value] {
+        self.body
+            .as_ref()
+            .and_then(|body| body.get("items"))
+            .and_then(serde_json::Value::as_array)
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+impl Display for CommandResponse {
+    /// The `body` in JSON. Empty if the `body` is empty.
+    /// The alternate form `{:#}` is pretty-printed.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.body {
+            Some(body) if f.alternate() => write!(f, "{body:#}"),
+            Some(body) => write!(f, "{body}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn empty() {
+        assert!(CommandResponse::new(None).is_empty());
+        assert!(CommandResponse::new(Some(json!({}))).is_empty());
+        assert!(CommandResponse::new(Some(json!(null))).is_empty());
+        assert_eq!(CommandResponse::new(Some(json!({}))).to_string(), "");
+    }
+
+    #[test]
+    fn typed() -> anyhow::Result<()> {
+        let response = CommandResponse::new(Some(json!({
+            "commandId": "CMD1",
+            "items": [{"deviceID": "D1", "code": 100}],
+        })));
+        assert!(!response.is_empty());
+        assert_eq!(response.command_id(), Some("CMD1"));
+        assert_eq!(response.items().len(), 1);
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body {
+            command_id: String,
+        }
+        let body: Body = response.body_as()?.unwrap();
+        assert_eq!(body.command_id, "CMD1");
+        Ok(())
+    }
+}
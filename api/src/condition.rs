@@ -0,0 +1,319 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display, str::FromStr, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::Device;
+
+/// A compiled conditional expression, such as `power=on` or `8.lightLevel<5`.
+///
+/// Following operators are supported.
+/// * `key`, `key=true`, and `key=false` for boolean types.
+/// * `=`, `<`, `<=`, `>`, and `>=` for numeric types.
+/// * `=` for string and other types.
+///
+/// The `key` can be prefixed by a device and a `.` (dot),
+/// such as `8.lightLevel<5`.
+/// The interpretation of the [`device()`][Condition::device()] is up to the caller;
+/// the [`switchbot-cli`] accepts the device number, the device ID, or an alias.
+///
+/// Please also see the [`switchbot-cli` documentation about the
+/// "if-command"](https://github.com/kojiishi/switchbot-rs/tree/main/cli#if-command).
+///
+/// [`switchbot-cli`]: https://github.com/kojiishi/switchbot-rs/tree/main/cli
+///
+/// # Examples
+/// ```
+/// # use std::collections::HashMap;
+/// # use switchbot_api::Condition;
+/// let condition: Condition = "power=on".parse()?;
+/// assert_eq!(condition.key(), "power");
+/// assert_eq!(condition.device(), None);
+///
+/// let status = HashMap::from([("power".to_string(), serde_json::json!("on"))]);
+/// assert!(condition.evaluate_status(&status)?);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Condition {
+    device: Option<String>,
+    key: String,
+    operator: String,
+    value: String,
+}
+
+impl Condition {
+    /// Parse a conditional expression.
+    /// Returns an error if the `condition` is not a valid expression.
+    pub fn new(condition: &str) -> anyhow::Result<Self> {
+        const RE_PAT: &str = r"^(?:([^.\s]+)\.)?([a-zA-Z]+)(\s*(=|<=?|>=?)\s*([a-zA-Z0-9]+))?$";
+        static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_PAT).unwrap());
+        if let Some(captures) = RE.captures(condition) {
+            let str_at = |i| captures.get(i).map_or_else(|| "", |m| m.as_str());
+            return Ok(Self {
+                device: captures.get(1).map(|m| m.as_str().to_string()),
+                key: str_at(2).into(),
+                operator: str_at(4).into(),
+                value: str_at(5).into(),
+            });
+        }
+        Err(anyhow::anyhow!(r#"Not a valid expression "{condition}""#))
+    }
+
+    /// The device prefix, if the expression has one.
+    /// For example, this is `Some("8")` for `8.lightLevel<5`.
+    pub fn device(&self) -> Option<&str> {
+        self.device.as_deref()
+    }
+
+    /// The status key this condition needs.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The operator, or an empty string if the expression is a boolean key.
+    pub fn operator(&self) -> &str {
+        &self.operator
+    }
+
+    /// The value to compare with, or an empty string if the expression is a boolean key.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Evaluate the condition against a status map.
+    /// The [`device()`][Condition::device()] is ignored;
+    /// the caller should pick the `status` of the right device.
+    ///
+    /// Returns an error if the [`key()`][Condition::key()] does not exist.
+    pub fn evaluate_status(&self, status: &impl StatusMap) -> anyhow::Result<bool> {
+        let value = status
+            .status_by_key(&self.key)
+            .ok_or_else(|| anyhow::anyhow!(r#"No status key "{}""#, self.key))?;
+        self.evaluate(&value)
+    }
+
+    /// Evaluate the condition against the value of the [`key()`][Condition::key()].
+    pub fn evaluate(&self, value: &serde_json::Value) -> anyhow::Result<bool> {
+        let value_str: Cow<'_, str> = match value {
+            serde_json::Value::Bool(b) => {
+                if self.operator.is_empty() {
+                    log::debug!("evaluate: bool {b}");
+                    return Ok(*b);
+                }
+                Cow::Owned(b.to_string())
+            }
+            serde_json::Value::Number(num) => {
+                if let Some(num_as_f64) = num.as_f64() {
+                    let value_as_f64: f64 = self.value.parse()?;
+                    return Self::eval_op(&self.operator, num_as_f64, value_as_f64);
+                }
+                Cow::Owned(value.to_string())
+            }
+            serde_json::Value::String(str) => Cow::Borrowed(str),
+            _ => Cow::Owned(value.to_string()),
+        };
+        if self.operator == "=" {
+            let result = value_str == self.value;
+            log::debug!(r#"evaluate: "{value_str}"="{}" -> {result}"#, self.value);
+            return Ok(result);
+        }
+        anyhow::bail!("Unsupported condition {self} for {value}");
+    }
+
+    fn eval_op<T: Display + PartialOrd>(op: &str, left: T, right: T) -> anyhow::Result<bool> {
+        let result = match op {
+            "=" => left == right,
+            "<" => left < right,
+            "<=" => left <= right,
+            ">" => left > right,
+            ">=" => left >= right,
+            _ => anyhow::bail!("Unsupported operator: {op}"),
+        };
+        log::debug!(r#"evaluate: "{left}"{op}"{right}" -> {result}"#);
+        Ok(result)
+    }
+}
+
+impl Display for Condition {
+    /// Write the expression in the same form as [`Condition::new()`] parses.
+    /// The alternate form (`{:#}`) puts spaces around the operator.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(device) = &self.device {
+            write!(f, "{device}.")?;
+        }
+        write!(f, "{}", self.key)?;
+        if !self.operator.is_empty() {
+            if f.alternate() {
+                write!(f, " {} {}", self.operator, self.value)?;
+            } else {
+                write!(f, "{}{}", self.operator, self.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(condition: &str) -> Result<Self, Self::Err> {
+        Self::new(condition)
+    }
+}
+
+impl TryFrom<&str> for Condition {
+    type Error = anyhow::Error;
+
+    fn try_from(condition: &str) -> Result<Self, Self::Error> {
+        Self::new(condition)
+    }
+}
+
+/// A map of status keys to values
+/// that a [`Condition`] can be evaluated against.
+///
+/// This is implemented for [`Device`],
+/// and for maps such as the `context` of webhook payloads.
+pub trait StatusMap {
+    /// Get the value of the `key`, or `None` if the `key` does not exist.
+    fn status_by_key(&self, key: &str) -> Option<serde_json::Value>;
+}
+
+impl StatusMap for Device {
+    fn status_by_key(&self, key: &str) -> Option<serde_json::Value> {
+        Device::status_by_key(self, key)
+    }
+}
+
+impl StatusMap for HashMap<String, serde_json::Value> {
+    fn status_by_key(&self, key: &str) -> Option<serde_json::Value> {
+        self.get(key).cloned()
+    }
+}
+
+impl StatusMap for serde_json::Map<String, serde_json::Value> {
+    fn status_by_key(&self, key: &str) -> Option<serde_json::Value> {
+        self.get(key).cloned()
+    }
+}
+
+impl StatusMap for serde_json::Value {
+    fn status_by_key(&self, key: &str) -> Option<serde_json::Value> {
+        self.get(key).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(str: &str) -> anyhow::Result<Condition> {
+        Condition::new(str)
+    }
+
+    fn from_key(key: &str) -> Condition {
+        Condition {
+            key: key.into(),
+            ..Default::default()
+        }
+    }
+
+    fn from_strs(key: &str, operator: &str, value: &str) -> Condition {
+        Condition {
+            device: None,
+            key: key.into(),
+            operator: operator.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn parse_condition() -> anyhow::Result<()> {
+        assert_eq!(parse("a")?, from_key("a"));
+        assert_eq!(parse("a=b")?, from_strs("a", "=", "b"));
+        assert_eq!(parse("a = b")?, from_strs("a", "=", "b"));
+        assert!(parse("a=").is_err());
+        assert!(parse("1=a").is_err());
+        assert_eq!(parse("a=12")?, from_strs("a", "=", "12"));
+        assert_eq!(parse("aZ=xZ2")?, from_strs("aZ", "=", "xZ2"));
+
+        assert_eq!(parse("a<b")?, from_strs("a", "<", "b"));
+        assert_eq!(parse("a>b")?, from_strs("a", ">", "b"));
+        assert_eq!(parse("a<=b")?, from_strs("a", "<=", "b"));
+        assert_eq!(parse("a>=b")?, from_strs("a", ">=", "b"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_device() -> anyhow::Result<()> {
+        let condition = parse("8.a<5")?;
+        assert_eq!(condition.device(), Some("8"));
+        assert_eq!(condition.key(), "a");
+        assert_eq!(parse("dev-1.a")?.device(), Some("dev-1"));
+        assert!(parse(".a").is_err());
+        assert!(parse("x.y.a").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> anyhow::Result<()> {
+        assert_eq!(parse("a")?.to_string(), "a");
+        assert_eq!(parse("a = b")?.to_string(), "a=b");
+        assert_eq!(parse("8.a<5")?.to_string(), "8.a<5");
+        assert_eq!(format!("{:#}", parse("8.a<5")?), "8.a < 5");
+        Ok(())
+    }
+
+    fn evaluate(expr: &str, value: impl serde::Serialize) -> anyhow::Result<bool> {
+        Condition::new(expr)?.evaluate(&serde_json::json!(value))
+    }
+
+    #[test]
+    fn evaluate_bool() -> anyhow::Result<()> {
+        assert!(evaluate("a", true)?);
+        assert!(!(evaluate("a", false)?));
+        assert!(evaluate("a=true", true)?);
+        assert!(!(evaluate("a=true", false)?));
+        assert!(evaluate("a=false", false)?);
+        assert!(evaluate("a>true", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_str() -> anyhow::Result<()> {
+        assert!(evaluate("a", "on").is_err());
+        assert!(evaluate("a=on", "on")?);
+        assert!(!(evaluate("a=on", "off")?));
+        assert!(evaluate("a>on", "off").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_num() -> anyhow::Result<()> {
+        assert!(evaluate("a", 123).is_err());
+        assert!(evaluate("a=123", 123)?);
+        assert!(!(evaluate("a=123", 124)?));
+
+        assert!(evaluate("a<123", 122)?);
+        assert!(!(evaluate("a<123", 123)?));
+        assert!(evaluate("a>123", 124)?);
+        assert!(!(evaluate("a>123", 123)?));
+        assert!(evaluate("a<=123", 123)?);
+        assert!(!(evaluate("a<=123", 124)?));
+        assert!(evaluate("a>=123", 123)?);
+        assert!(!(evaluate("a>=123", 122)?));
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_status() -> anyhow::Result<()> {
+        let condition = parse("power=on")?;
+        let json = serde_json::json!({"power": "on", "battery": 80});
+        assert!(condition.evaluate_status(&json)?);
+        assert!(condition.evaluate_status(json.as_object().unwrap())?);
+        let map = HashMap::from([("power".to_string(), serde_json::json!("off"))]);
+        assert!(!condition.evaluate_status(&map)?);
+        assert!(parse("x")?.evaluate_status(&map).is_err());
+        Ok(())
+    }
+}
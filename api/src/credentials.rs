@@ -0,0 +1,82 @@
+use crate::SecretString;
+
+/// The authentication information for the [SwitchBot API].
+///
+/// Please refer to the [SwitchBot documentation about
+/// how to obtain the token and secret key][token-secret].
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+/// [token-secret]: https://github.com/OpenWonderLabs/SwitchBotAPI#getting-started
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Credentials {
+    token: SecretString,
+    secret: SecretString,
+}
+
+impl Credentials {
+    /// Construct a new instance from the token and the secret.
+    pub fn new(token: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            token: SecretString::from(token.into()),
+            secret: SecretString::from(secret.into()),
+        }
+    }
+
+    /// The token.
+    pub fn token(&self) -> &str {
+        self.token.expose()
+    }
+
+    /// The secret.
+    pub fn secret(&self) -> &str {
+        self.secret.expose()
+    }
+
+    /// True if either the token or the secret is empty.
+    pub fn is_empty(&self) -> bool {
+        self.token.is_empty() || self.secret.is_empty()
+    }
+}
+
+/// A source of [`Credentials`],
+/// such as environment variables, files, or password managers.
+///
+/// Please see [`SwitchBot::new_with_credential_provider()`][crate::SwitchBot::new_with_credential_provider()].
+///
+/// # Examples
+/// ```
+/// # use switchbot_api::{CredentialProvider, Credentials};
+/// struct EnvProvider;
+///
+/// impl CredentialProvider for EnvProvider {
+///     fn credentials(&self) -> anyhow::Result<Credentials> {
+///         Ok(Credentials::new(
+///             std::env::var("MY_TOKEN")?,
+///             std::env::var("MY_SECRET")?,
+///         ))
+///     }
+/// }
+/// ```
+pub trait CredentialProvider {
+    /// Get the [`Credentials`].
+    fn credentials(&self) -> anyhow::Result<Credentials>;
+}
+
+impl CredentialProvider for Credentials {
+    fn credentials(&self) -> anyhow::Result<Credentials> {
+        Ok(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug() {
+        let credentials = Credentials::new("my_token", "my_secret");
+        let debug = format!("{credentials:?}");
+        assert!(!debug.contains("my_token"));
+        assert!(!debug.contains("my_secret"));
+    }
+}
@@ -3,7 +3,6 @@ use std::{
     fmt::Display,
     io,
     sync::{Arc, RwLock, RwLockReadGuard, Weak},
-    thread,
     time::{Duration, Instant},
 };
 
@@ -33,6 +32,12 @@ pub struct Device {
     #[serde(skip)]
     status: RwLock<HashMap<String, serde_json::Value>>,
 
+    #[serde(skip)]
+    is_status_inferred: bool,
+
+    #[serde(skip)]
+    account: String,
+
     #[serde(skip)]
     service: Weak<SwitchBotService>,
 
@@ -42,6 +47,11 @@ pub struct Device {
 
 static MIN_INTERVAL_FOR_REMOTE_DEVICES: RwLock<Duration> = RwLock::new(Duration::from_millis(500));
 
+/// The first and the maximum intervals to poll the status
+/// in [`Device::command_and_wait()`].
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(8);
+
 impl Device {
     pub fn set_default_min_internal_for_remote_devices(min_interval: Duration) {
         *MIN_INTERVAL_FOR_REMOTE_DEVICES.write().unwrap() = min_interval;
@@ -98,6 +108,18 @@ impl Device {
         &self.hub_device_id
     }
 
+    /// The name of the account this device belongs to.
+    /// Please see [`SwitchBot::add_account()`].
+    ///
+    /// This is empty for the account set by [`SwitchBot::set_authentication()`].
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    pub(crate) fn set_account(&mut self, account: &str) {
+        self.account = account.into();
+    }
+
     fn service(&self) -> anyhow::Result<Arc<SwitchBotService>> {
         self.service
             .upgrade()
@@ -112,6 +134,14 @@ impl Device {
     ///
     /// Please also see the [`CommandRequest`].
     ///
+    /// When the command succeeds, the cached status is cleared
+    /// by [`clear_status()`][Device::clear_status()],
+    /// because the command may have changed it.
+    /// [`status_by_key()`][Device::status_by_key()] returns `None`
+    /// until [`update_status()`][Device::update_status()] is called again.
+    /// The status [inferred][Device::is_status_inferred()] from the commands
+    /// is updated instead.
+    ///
     /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
     ///
     /// # Examples
@@ -120,32 +150,180 @@ impl Device {
     /// # async fn turn_on(device: &Device) -> anyhow::Result<()> {
     /// let command = CommandRequest { command: "turnOn".into(), ..Default::default() };
     /// device.command(&command).await?;
+    /// device.update_status().await?;
+    /// println!("Power = {}", device.status_by_key("power").unwrap());
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn command(&self, command: &CommandRequest) -> anyhow::Result<()> {
+    pub async fn command(&self, command: &CommandRequest) -> anyhow::Result<CommandResponse> {
         if self.is_remote() {
             // For remote devices, give some delays between commands.
-            self.sleep_for_interval();
+            self.sleep_for_interval().await;
         }
 
-        self.service()?.command(self.device_id(), command).await?;
+        let service = self.service()?;
+        let start = Instant::now();
+        let result = service.command(self.device_id(), command).await;
+        if let Some(audit_log) = service.audit_log() {
+            audit_log.record(self, command, &result, start.elapsed());
+        }
+        let response = result?;
 
         if self.is_remote() {
             self.update_interval();
         }
-        Ok(())
+        if self.is_status_inferred {
+            let mut status = self.status.write().unwrap();
+            if ShadowState::apply_command(&mut status, command) {
+                log::debug!("command: inferred status {status:?} for {self}");
+            }
+        } else {
+            // The command may have changed the status.
+            self.clear_status();
+        }
+        Ok(response)
+    }
+
+    /// Send the `command` unless the status already matches
+    /// what the `command` results in.
+    /// Returns `None` if the `command` is skipped.
+    ///
+    /// This is useful to avoid using up the quota,
+    /// or to avoid toggling the physical switch of Bot devices.
+    /// The status is fetched by [`update_status()`][Device::update_status()]
+    /// only if it is not cached, so that this function doesn't cost
+    /// an extra API call after `update_status()` or another `command_if_needed()`.
+    /// The cache is cleared by [`command()`][Device::command()];
+    /// call [`clear_status()`][Device::clear_status()] or `update_status()`
+    /// to force refreshing the status that may be changed by others.
+    /// Commands whose results are unknown are always sent.
+    /// Please see [`expected_status()`][Device::expected_status()].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::{CommandRequest, Device};
+    /// # async fn turn_on(device: &Device) -> anyhow::Result<()> {
+    /// if device.command_if_needed(&CommandRequest::from("turnOn")).await?.is_none() {
+    ///     println!("{device} is already on");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn command_if_needed(
+        &self,
+        command: &CommandRequest,
+    ) -> anyhow::Result<Option<CommandResponse>> {
+        if let Some(condition) = self.expected_status(command) {
+            if !self.has_status() {
+                self.update_status().await?;
+            }
+            if self.evaluate(&condition).unwrap_or(false) {
+                log::debug!("command_if_needed: skip {command} for {self}, {condition}");
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.command(command).await?))
+    }
+
+    /// Send the `command`, and wait until the status satisfies the `expected`
+    /// condition, or the `timeout` elapses.
+    ///
+    /// Some devices such as curtains and locks respond to the commands
+    /// before they physically finish.
+    /// This function polls the status by
+    /// [`update_status()`][Device::update_status()] with backoff,
+    /// and returns an error with the last observed status on timeout.
+    /// The [`expected_status()`][Device::expected_status()] may be useful
+    /// for the `expected` condition.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use switchbot_api::{CommandRequest, Condition, Device};
+    /// # async fn close(curtain: &Device) -> anyhow::Result<()> {
+    /// let command = CommandRequest::from("setPosition:0,ff,100");
+    /// let expected = Condition::new("slidePosition=100")?;
+    /// curtain.command_and_wait(&command, &expected, Duration::from_secs(30)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn command_and_wait(
+        &self,
+        command: &CommandRequest,
+        expected: &Condition,
+        timeout: Duration,
+    ) -> anyhow::Result<CommandResponse> {
+        let response = self.command(command).await?;
+        let start = Instant::now();
+        let mut interval = MIN_POLL_INTERVAL;
+        loop {
+            self.update_status().await?;
+            if self.evaluate(expected).unwrap_or(false) {
+                log::debug!("command_and_wait: {expected} in {:?}", start.elapsed());
+                return Ok(response);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                let last = self
+                    .status_by_key(expected.key())
+                    .map_or_else(|| "none".into(), |value| value.to_string());
+                anyhow::bail!(
+                    "Timed out waiting for {expected} of {self} after {elapsed:?}, the last {} is {last}",
+                    expected.key()
+                );
+            }
+            let duration = interval.min(timeout - elapsed);
+            log::debug!("command_and_wait: sleep {duration:?} for {expected}");
+            futures_timer::Delay::new(duration).await;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+
+    /// The [`Condition`] the status satisfies after the `command`,
+    /// or `None` if it's unknown.
+    /// * `turnOn` and `turnOff` result in `power=on` and `power=off`.
+    /// * `lock` and `unlock` result in `lockState=locked` and `lockState=unlocked`.
+    /// * `setPosition` of curtains and roller shades results in `slidePosition`.
+    /// * `setBrightness` results in `brightness`.
+    /// ```
+    /// # use switchbot_api::{CommandRequest, Device};
+    /// # fn test(device: &Device) {
+    /// let condition = device.expected_status(&CommandRequest::from("turnOn"));
+    /// assert_eq!(condition.unwrap().to_string(), "power=on");
+    /// # }
+    /// ```
+    pub fn expected_status(&self, command: &CommandRequest) -> Option<Condition> {
+        if !command.command_type.is_empty() && command.command_type != "command" {
+            return None;
+        }
+        let parameter = command.parameter.as_str();
+        let (key, value) = match command.command.as_str() {
+            "turnOn" => ("power", "on"),
+            "turnOff" => ("power", "off"),
+            "lock" => ("lockState", "locked"),
+            "unlock" => ("lockState", "unlocked"),
+            "setPosition" => match self.device_type() {
+                // The parameter is "index,mode,position".
+                "Curtain" | "Curtain3" => ("slidePosition", parameter.rsplit(',').next()?),
+                "Roller Shade" => ("slidePosition", parameter),
+                _ => return None,
+            },
+            "setBrightness" => ("brightness", parameter),
+            _ => return None,
+        };
+        Condition::new(&format!("{key}={}", value.trim())).ok()
     }
 
-    fn sleep_for_interval(&self) {
+    async fn sleep_for_interval(&self) {
         let min_interval = *MIN_INTERVAL_FOR_REMOTE_DEVICES.read().unwrap();
-        let last_command_time = self.last_command_time.read().unwrap();
-        if let Some(last_time) = *last_command_time {
+        // Don't hold the lock while sleeping.
+        let last_command_time = *self.last_command_time.read().unwrap();
+        if let Some(last_time) = last_command_time {
             let elapsed = last_time.elapsed();
             if elapsed < min_interval {
                 let duration = min_interval - elapsed;
                 log::debug!("command: sleep {duration:?} for {self}");
-                thread::sleep(duration);
+                futures_timer::Delay::new(duration).await;
             }
         }
     }
@@ -173,7 +351,13 @@ impl Device {
     ///
     /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
     /// [device status]: https://github.com/OpenWonderLabs/SwitchBotAPI#get-device-status
+    ///
+    /// If the [`is_status_inferred()`][Device::is_status_inferred()],
+    /// this function does nothing.
     pub async fn update_status(&self) -> anyhow::Result<()> {
+        if self.is_status_inferred {
+            return Ok(());
+        }
         let status = self.service()?.status(self.device_id()).await?;
         if status.is_none() {
             log::warn!("The query succeeded with no status");
@@ -186,13 +370,51 @@ impl Device {
         Ok(())
     }
 
+    /// True if the status is inferred from the commands sent,
+    /// rather than retrieved from the [SwitchBot API].
+    ///
+    /// This is true for infrared remote devices
+    /// when [`SwitchBot::set_shadow_state()`] is set.
+    /// Please see [`ShadowState`].
+    ///
+    /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+    pub fn is_status_inferred(&self) -> bool {
+        self.is_status_inferred
+    }
+
+    /// Start inferring the status from the commands, with the initial `status`.
+    pub(crate) fn set_inferred_status(&mut self, status: HashMap<String, serde_json::Value>) {
+        self.is_status_inferred = true;
+        *self.status.write().unwrap() = status;
+    }
+
+    pub(crate) fn inferred_status(&self) -> Option<HashMap<String, serde_json::Value>> {
+        self.is_status_inferred.then(|| self.status().clone())
+    }
+
+    /// True if the status is cached by [`update_status()`][Device::update_status()],
+    /// or [`is_status_inferred()`][Device::is_status_inferred()].
+    pub fn has_status(&self) -> bool {
+        self.is_status_inferred || !self.status().is_empty()
+    }
+
+    /// Clear the cached status,
+    /// so that [`command_if_needed()`][Device::command_if_needed()] fetches it again.
+    /// The inferred status is not cleared.
+    pub fn clear_status(&self) {
+        if !self.is_status_inferred {
+            self.status.write().unwrap().clear();
+        }
+    }
+
     fn status(&self) -> RwLockReadGuard<'_, HashMap<String, serde_json::Value>> {
         self.status.read().unwrap()
     }
 
     /// Get the value of a key from the [device status].
     ///
-    /// The [`update_status()`][Device::update_status()] must be called prior to this function.
+    /// The [`update_status()`][Device::update_status()] must be called prior to this function,
+    /// and after [`command()`][Device::command()].
     ///
     /// # Examples
     /// ```no_run
@@ -208,6 +430,18 @@ impl Device {
         self.status().get(key).cloned()
     }
 
+    /// The keys of the [device status], in the alphabetical order.
+    ///
+    /// The [`update_status()`][Device::update_status()] must be called prior to this function,
+    /// and after [`command()`][Device::command()].
+    ///
+    /// [device status]: https://github.com/OpenWonderLabs/SwitchBotAPI#get-device-status
+    pub fn status_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.status().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
     /// Evaluate a conditional expression.
     ///
     /// Following operators are supported.
@@ -220,7 +454,8 @@ impl Device {
     /// Please also see the [`switchbot-cli` documentation about the
     /// "if-command"](https://github.com/kojiishi/switchbot-rs/tree/main/cli#if-command).
     ///
-    /// The [`update_status()`][Device::update_status()] must be called prior to this function.
+    /// The [`update_status()`][Device::update_status()] must be called prior to this function,
+    /// and after [`command()`][Device::command()].
     ///
     /// # Examples
     /// ```no_run
@@ -232,16 +467,28 @@ impl Device {
     /// # }
     /// ```
     pub fn eval_condition(&self, condition: &str) -> anyhow::Result<bool> {
-        let condition = ConditionalExpression::try_from(condition)?;
+        self.evaluate(&Condition::new(condition)?)
+    }
+
+    /// Evaluate a compiled [`Condition`].
+    ///
+    /// This is the same as [`eval_condition()`][Device::eval_condition()],
+    /// except that the `condition` is parsed only once.
+    /// The [`Condition::device()`] is ignored.
+    ///
+    /// The [`update_status()`][Device::update_status()] must be called prior to this function,
+    /// and after [`command()`][Device::command()].
+    pub fn evaluate(&self, condition: &Condition) -> anyhow::Result<bool> {
         let value = self
-            .status_by_key(condition.key)
-            .ok_or_else(|| anyhow::anyhow!(r#"No status key "{}" for {self}"#, condition.key))?;
+            .status_by_key(condition.key())
+            .ok_or_else(|| anyhow::anyhow!(r#"No status key "{}" for {self}"#, condition.key()))?;
         condition.evaluate(&value)
     }
 
     /// Write the list of the [device status] to the `writer`.
     ///
-    /// The [`update_status()`][Device::update_status()] must be called prior to this function.
+    /// The [`update_status()`][Device::update_status()] must be called prior to this function,
+    /// and after [`command()`][Device::command()].
     ///
     /// # Examples
     /// ```no_run
@@ -271,7 +518,11 @@ impl Device {
         }
         let status = self.status();
         if !status.is_empty() {
-            writeln!(buf, "Status:")?;
+            if self.is_status_inferred {
+                writeln!(buf, "Status (inferred):")?;
+            } else {
+                writeln!(buf, "Status:")?;
+            }
             for (key, value) in status.iter() {
                 writeln!(buf, "  {key}: {value}")?;
             }
@@ -1,25 +1,28 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
 };
 
-use crate::{CommandRequest, Device, Markdown};
+use crate::{CommandRequest, Device, HelpImporter, Markdown, ParameterSchema};
 
 /// Human-readable description of a [`CommandRequest`].
 ///
 /// Please see [`Help::command_helps()`] for how to get this struct.
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct CommandHelp {
     command: CommandRequest,
     description: Markdown,
+    #[serde(skip)]
+    parameter_schema: ParameterSchema,
+    #[serde(skip)]
+    scope: CommandScope,
+    #[serde(skip)]
+    source: HelpSource,
 }
 
 impl CommandHelp {
-    fn empty_vec() -> &'static Vec<CommandHelp> {
-        static EMPTY: Vec<CommandHelp> = Vec::new();
-        &EMPTY
-    }
-
     /// The [`CommandRequest`].
     /// Note that this may contain human-readable text
     /// and may not be able to send to the SwitchBot API directly.
@@ -31,15 +34,226 @@ impl CommandHelp {
     pub fn description(&self) -> &Markdown {
         &self.description
     }
+
+    /// The [`ParameterSchema`] parsed from the
+    /// [`CommandRequest::parameter`] of the [`command()`][CommandHelp::command()].
+    pub fn parameter_schema(&self) -> &ParameterSchema {
+        &self.parameter_schema
+    }
+
+    fn update_parameter_schema(&mut self) {
+        self.parameter_schema = ParameterSchema::parse(&self.command.parameter);
+    }
+
+    /// Where this command is inherited from.
+    pub fn scope(&self) -> CommandScope {
+        self.scope
+    }
+
+    /// Where this help is loaded from.
+    pub fn source(&self) -> &HelpSource {
+        &self.source
+    }
+
+    /// The command and its description,
+    /// with the Markdown rendered by [`Markdown::text()`].
+    pub fn text(&self, is_ansi: bool) -> String {
+        let command = Markdown::new(&self.command.to_string());
+        indented_text(&command.text(is_ansi), &self.description.text(is_ansi))
+    }
+
+    fn is_same_command(&self, other: &CommandHelp) -> bool {
+        self.command.command == other.command.command
+            && self.command.command_type == other.command.command_type
+    }
+}
+
+/// Where a [`CommandHelp`] is inherited from.
+///
+/// Infrared remote devices inherit commands
+/// from the category-wide and the generic commands.
+/// Please see [`Help::command_helps()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CommandScope {
+    /// The command is specific to the device type.
+    #[default]
+    DeviceType,
+    /// The command is for all home appliance types
+    /// of infrared remote devices, except "Others".
+    AllAppliances,
+    /// The command is for the "Others" type of infrared remote devices,
+    /// which is generic guidance for `customize` buttons.
+    Others,
+}
+
+impl CommandScope {
+    pub(crate) const ALL_APPLIANCES_KEY: &str = "All home appliance types except Others";
+    pub(crate) const OTHERS_KEY: &str = "Others";
+
+    fn from_ir_key(key: &str) -> Self {
+        match key {
+            Self::ALL_APPLIANCES_KEY => Self::AllAppliances,
+            Self::OTHERS_KEY => Self::Others,
+            _ => Self::DeviceType,
+        }
+    }
+}
+
+impl Display for CommandScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceType => write!(f, "Device type"),
+            Self::AllAppliances => write!(f, "{}", Self::ALL_APPLIANCES_KEY),
+            Self::Others => write!(f, "{}", Self::OTHERS_KEY),
+        }
+    }
 }
 
 impl Display for CommandHelp {
+    /// Write the command and its description.
+    /// The alternate form (`{:#}`) renders the Markdown as plain text;
+    /// please see [`text()`][CommandHelp::text()].
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.command)?;
-        for description in self.description.to_string().split('\n') {
-            write!(f, "\n    {description}")?;
+        if f.alternate() {
+            return write!(f, "{}", self.text(false));
+        }
+        let text = indented_text(&self.command.to_string(), &self.description.to_string());
+        write!(f, "{text}")
+    }
+}
+
+/// The `head` followed by the lines of the `description` indented.
+fn indented_text(head: &str, description: &str) -> String {
+    let mut text = head.to_string();
+    for line in description.split('\n') {
+        text.push_str("\n    ");
+        text.push_str(line);
+    }
+    text
+}
+
+/// Human-readable description of a key in the [device status].
+///
+/// Please see [`Help::status_helps()`] for how to get this struct.
+///
+/// [device status]: https://github.com/OpenWonderLabs/SwitchBotAPI#get-device-status
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct StatusHelp {
+    key: String,
+    #[serde(rename = "type")]
+    value_type: String,
+    #[serde(default)]
+    unit: String,
+    #[serde(default)]
+    range: String,
+    description: Markdown,
+    #[serde(skip)]
+    source: HelpSource,
+}
+
+impl StatusHelp {
+    /// The status key, such as `lightLevel`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The type of the value, such as `Integer` or `String`.
+    pub fn value_type(&self) -> &str {
+        &self.value_type
+    }
+
+    /// The unit of the value, such as `°C` or `%`.
+    /// This is empty if the value has no units, or if it's unknown.
+    pub fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    /// The range of the value, such as `0-100`.
+    /// This is empty if it's unknown.
+    pub fn range(&self) -> &str {
+        &self.range
+    }
+
+    /// The human-readable description of the [`key()`][StatusHelp::key()].
+    pub fn description(&self) -> &Markdown {
+        &self.description
+    }
+
+    /// Where this help is loaded from.
+    pub fn source(&self) -> &HelpSource {
+        &self.source
+    }
+
+    /// The type, the unit, and the range in a short form, such as `Integer, %, 0-100`.
+    pub fn summary(&self) -> String {
+        [self.value_type(), self.unit(), self.range()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The key, the [`summary()`][StatusHelp::summary()], and the description,
+    /// with the Markdown rendered by [`Markdown::text()`].
+    pub fn text(&self, is_ansi: bool) -> String {
+        indented_text(&self.head(), &self.description.text(is_ansi))
+    }
+
+    fn head(&self) -> String {
+        format!("{} ({})", self.key, self.summary())
+    }
+}
+
+impl Display for StatusHelp {
+    /// Write the key, the [`summary()`][StatusHelp::summary()], and the description.
+    /// The alternate form (`{:#}`) renders the Markdown as plain text;
+    /// please see [`text()`][StatusHelp::text()].
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.text(false));
+        }
+        let text = indented_text(&self.head(), &self.description.to_string());
+        write!(f, "{text}")
+    }
+}
+
+/// A [`CommandHelp`] found by [`Help::find_command()`] or [`Help::search()`].
+#[derive(Clone, Copy, Debug)]
+pub struct CommandMatch<'a> {
+    /// The device type, or the remote type if `is_remote` is `true`.
+    pub device_type: &'a str,
+    /// `true` if the command is for infrared remote devices.
+    pub is_remote: bool,
+    /// The [`CommandHelp`].
+    pub command_help: &'a CommandHelp,
+}
+
+impl Display for CommandMatch<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_remote {
+            write!(f, "{} (infrared remote)", self.device_type)
+        } else {
+            write!(f, "{}", self.device_type)
+        }
+    }
+}
+
+/// Where a [`CommandHelp`] or a [`StatusHelp`] is loaded from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum HelpSource {
+    /// The data built into this crate.
+    #[default]
+    BuiltIn,
+    /// The file loaded by [`Help::load_from_path()`].
+    Path(PathBuf),
+}
+
+impl Display for HelpSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BuiltIn => write!(f, "built-in"),
+            Self::Path(path) => write!(f, "{}", path.display()),
         }
-        Ok(())
     }
 }
 
@@ -48,10 +262,16 @@ impl Display for CommandHelp {
 /// Please see [`Help::command_helps()`] for an example.
 ///
 /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
-#[derive(Default, serde::Deserialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct Help {
+    version: u32,
+    #[serde(default)]
     commands: HashMap<String, Vec<CommandHelp>>,
+    #[serde(default)]
     commands_ir: HashMap<String, Vec<CommandHelp>>,
+    #[serde(default)]
+    status: HashMap<String, Vec<StatusHelp>>,
+    #[serde(default)]
     device_type_aliases: HashMap<String, Vec<String>>,
 }
 
@@ -61,6 +281,8 @@ impl Debug for Help {
         self.fmt_commands(&self.commands, f)?;
         writeln!(f, "commands (IR):")?;
         self.fmt_commands(&self.commands_ir, f)?;
+        writeln!(f, "status:")?;
+        self.fmt_commands(&self.status, f)?;
         writeln!(f, "aliases:")?;
         for (device_type, aliases) in &self.device_type_aliases {
             writeln!(f, "- {device_type} -> {aliases:?}")?;
@@ -70,13 +292,150 @@ impl Debug for Help {
 }
 
 impl Help {
+    /// The version of the JSON schema this crate can load.
+    pub const SCHEMA_VERSION: u32 = 1;
+
     /// Loads the documentations from the [SwitchBot API] local data file.
     ///
     /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
     pub async fn load() -> anyhow::Result<Self> {
+        Self::load_built_in()
+    }
+
+    /// The synchronous version of [`load()`][Help::load()].
+    pub(crate) fn load_built_in() -> anyhow::Result<Self> {
         let json_str = include_str!("help_data.json");
+        Self::from_json_str(json_str, HelpSource::BuiltIn)
+    }
+
+    /// Loads the documentations from a JSON file.
+    ///
+    /// The file has the same format as the built-in data,
+    /// but all keys other than `version` are optional.
+    /// Returns an error if the `version` is newer than [`Help::SCHEMA_VERSION`].
+    ///
+    /// Please see [`merge()`][Help::merge()] to add or override the built-in data.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::Help;
+    /// # async fn help() -> anyhow::Result<()> {
+    /// let mut help = Help::load().await?;
+    /// help.merge(Help::load_from_path("my_help.json")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        log::debug!("help: load {path:?}");
+        let json_str = fs::read_to_string(path)?;
+        Self::from_json_str(&json_str, HelpSource::Path(path.to_path_buf()))
+            .map_err(|error| anyhow::anyhow!("{}: {error}", path.display()))
+    }
+
+    /// Imports the documentations from a local checkout of the [SwitchBot API]
+    /// repository, by parsing the Markdown documents in its `devices` directory.
+    ///
+    /// Use [`to_json_string()`][Help::to_json_string()] to save the result
+    /// to a file for [`load_from_path()`][Help::load_from_path()].
+    ///
+    /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+    pub fn from_markdown_docs(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        log::debug!("help: import {dir:?}");
+        let json = HelpImporter::import_dir(dir)?;
+        let help: Self = serde_json::from_value(json)?;
+        help.init(HelpSource::Path(dir.to_path_buf()))
+    }
+
+    /// Serialize to the JSON format that
+    /// [`load_from_path()`][Help::load_from_path()] can load.
+    pub fn to_json_string(&self) -> anyhow::Result<String> {
+        // Convert to `Value` first to sort the keys.
+        let json = serde_json::to_value(self)?;
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    /// The number of device types and infrared remote types.
+    pub fn num_device_types(&self) -> usize {
+        self.commands.len() + self.commands_ir.len()
+    }
+
+    fn from_json_str(json_str: &str, source: HelpSource) -> anyhow::Result<Self> {
         let help: Self = serde_json::from_str(json_str)?;
-        Ok(help)
+        help.init(source)
+    }
+
+    fn init(mut self, source: HelpSource) -> anyhow::Result<Self> {
+        let help = &mut self;
+        if help.version == 0 || help.version > Self::SCHEMA_VERSION {
+            anyhow::bail!(
+                "Unsupported help data version {} (supported: 1-{})",
+                help.version,
+                Self::SCHEMA_VERSION
+            );
+        }
+        for command_help in help
+            .commands
+            .values_mut()
+            .chain(help.commands_ir.values_mut())
+            .flatten()
+        {
+            command_help.update_parameter_schema();
+            command_help.source = source.clone();
+        }
+        for (key, command_helps) in help.commands_ir.iter_mut() {
+            let scope = CommandScope::from_ir_key(key);
+            for command_help in command_helps {
+                command_help.scope = scope;
+            }
+        }
+        for status_help in help.status.values_mut().flatten() {
+            status_help.source = source.clone();
+        }
+        Ok(self)
+    }
+
+    /// Merge `other` into this instance.
+    ///
+    /// Commands with the same command and command type,
+    /// and status with the same key, replace the existing ones.
+    /// Others are appended.
+    /// The `device_type_aliases` of `other` take precedence over existing ones.
+    pub fn merge(&mut self, other: Help) {
+        Self::merge_map(
+            &mut self.commands,
+            other.commands,
+            CommandHelp::is_same_command,
+        );
+        Self::merge_map(
+            &mut self.commands_ir,
+            other.commands_ir,
+            CommandHelp::is_same_command,
+        );
+        Self::merge_map(&mut self.status, other.status, |a, b| a.key == b.key);
+        for (device_type, aliases) in other.device_type_aliases {
+            let existing = self.device_type_aliases.entry(device_type).or_default();
+            existing.retain(|alias| !aliases.contains(alias));
+            existing.splice(0..0, aliases);
+        }
+    }
+
+    fn merge_map<T>(
+        map: &mut HashMap<String, Vec<T>>,
+        other: HashMap<String, Vec<T>>,
+        is_same: impl Fn(&T, &T) -> bool,
+    ) {
+        for (device_type, items) in other {
+            let list = map.entry(device_type).or_default();
+            for item in items {
+                if let Some(existing) = list.iter_mut().find(|existing| is_same(existing, &item)) {
+                    *existing = item;
+                } else {
+                    list.push(item);
+                }
+            }
+        }
     }
 
     /// Adds a device type alias.
@@ -91,6 +450,13 @@ impl Help {
     /// Get a list of [`CommandHelp`] for a [`Device`].
     /// Returns an empty `Vec` if no [`CommandHelp`]s are found.
     ///
+    /// For infrared remote devices,
+    /// the list contains the commands specific to the remote type,
+    /// then the commands for all home appliance types,
+    /// then the generic `customize` button guidance.
+    /// Duplicated commands are removed.
+    /// The [`CommandHelp::scope()`] tells where each command is inherited from.
+    ///
     /// # Examples
     /// ```no_run
     /// # use switchbot_api::{Device, Help};
@@ -103,43 +469,196 @@ impl Help {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn command_helps(&self, device: &Device) -> &Vec<CommandHelp> {
+    pub fn command_helps(&self, device: &Device) -> Vec<&CommandHelp> {
         if device.is_remote() {
             return self.command_helps_by_remote_type(device.remote_type());
         }
         self.command_helps_by_device_type(device.device_type())
     }
 
-    fn command_helps_by_device_type(&self, device_type: &str) -> &Vec<CommandHelp> {
-        if let Some(commands) = self.commands.get(device_type) {
-            return commands;
+    /// Get a list of [`CommandHelp`] for a device type,
+    /// such as the [`Device::device_type()`] or the values of
+    /// [`device_types()`][Help::device_types()].
+    /// Returns an empty `Vec` if no [`CommandHelp`]s are found.
+    ///
+    /// This is useful to look up commands without a [`Device`].
+    pub fn command_helps_by_device_type(&self, device_type: &str) -> Vec<&CommandHelp> {
+        self.get_by_device_type(&self.commands, device_type)
+            .map_or_else(Vec::new, |helps| helps.iter().collect())
+    }
+
+    /// Get a list of [`StatusHelp`] for a [`Device`].
+    /// Returns an empty slice if no [`StatusHelp`]s are found,
+    /// including when the [`Device`] is an infrared remote device.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::{Device, Help};
+    /// # async fn help(device: &Device) -> anyhow::Result<()> {
+    /// let help = Help::load().await?;
+    /// for status_help in help.status_helps(device) {
+    ///   println!("{}", status_help);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn status_helps(&self, device: &Device) -> &[StatusHelp] {
+        if device.is_remote() {
+            return &[];
+        }
+        self.status_helps_by_device_type(device.device_type())
+    }
+
+    /// Get a list of [`StatusHelp`] for a device type.
+    /// Returns an empty slice if no [`StatusHelp`]s are found.
+    pub fn status_helps_by_device_type(&self, device_type: &str) -> &[StatusHelp] {
+        self.get_by_device_type(&self.status, device_type)
+            .map_or(&[], |helps| helps.as_slice())
+    }
+
+    /// Get the [`StatusHelp`] for the `key` of a [`Device`].
+    pub fn status_help(&self, device: &Device, key: &str) -> Option<&StatusHelp> {
+        self.status_helps(device)
+            .iter()
+            .find(|status_help| status_help.key == key)
+    }
+
+    fn get_by_device_type<'a, T>(
+        &self,
+        map: &'a HashMap<String, T>,
+        device_type: &str,
+    ) -> Option<&'a T> {
+        if let Some(value) = map.get(device_type) {
+            return Some(value);
         }
         if let Some(aliases) = self.device_type_aliases.get(device_type) {
             for alias in aliases {
-                if let Some(commands) = self.commands.get(alias) {
-                    return commands;
+                if let Some(value) = map.get(alias) {
+                    return Some(value);
                 }
             }
         }
-        CommandHelp::empty_vec()
+        None
     }
 
-    fn command_helps_by_remote_type(&self, remote_type: &str) -> &Vec<CommandHelp> {
-        if let Some(commands) = self.commands_ir.get(remote_type) {
-            return commands;
-        }
+    /// The sorted list of device types that have [`CommandHelp`]s.
+    ///
+    /// Device types that have only aliases are not included.
+    pub fn device_types(&self) -> Vec<&str> {
+        let mut device_types: Vec<&str> = self.commands.keys().map(String::as_str).collect();
+        device_types.sort();
+        device_types
+    }
+
+    /// The sorted list of infrared remote types that have [`CommandHelp`]s.
+    pub fn ir_types(&self) -> Vec<&str> {
+        let mut ir_types: Vec<&str> = self
+            .commands_ir
+            .keys()
+            .map(String::as_str)
+            .filter(|key| CommandScope::from_ir_key(key) != CommandScope::AllAppliances)
+            .collect();
+        ir_types.sort();
+        ir_types
+    }
+
+    /// Find the commands of the `command` name across all device types,
+    /// including infrared remote types.
+    ///
+    /// The result is sorted by the device types,
+    /// physical devices first.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::Help;
+    /// # async fn help() -> anyhow::Result<()> {
+    /// let help = Help::load().await?;
+    /// for found in help.find_command("setColorTemperature") {
+    ///   println!("{}: {}", found.device_type, found.command_help);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_command(&self, command: &str) -> Vec<CommandMatch<'_>> {
+        self.find_commands_by(|command_help| command_help.command.command == command)
+    }
+
+    /// Search the command names and the descriptions across all device types,
+    /// including infrared remote types.
+    /// The search is case-insensitive.
+    ///
+    /// The result is sorted in the same way as [`find_command()`][Help::find_command()].
+    pub fn search(&self, text: &str) -> Vec<CommandMatch<'_>> {
+        let text = text.to_lowercase();
+        self.find_commands_by(|command_help| {
+            command_help.command.command.to_lowercase().contains(&text)
+                || command_help
+                    .description
+                    .markdown()
+                    .to_lowercase()
+                    .contains(&text)
+        })
+    }
+
+    fn find_commands_by(&self, predicate: impl Fn(&CommandHelp) -> bool) -> Vec<CommandMatch<'_>> {
+        let commands = self.commands.iter().map(|(key, helps)| (key, false, helps));
+        let commands_ir = self
+            .commands_ir
+            .iter()
+            .map(|(key, helps)| (key, true, helps));
+        let mut matches: Vec<CommandMatch> = commands
+            .chain(commands_ir)
+            .flat_map(|(device_type, is_remote, command_helps)| {
+                command_helps
+                    .iter()
+                    .filter(|command_help| predicate(command_help))
+                    .map(move |command_help| CommandMatch {
+                        device_type,
+                        is_remote,
+                        command_help,
+                    })
+            })
+            .collect();
+        matches.sort_by_key(|found| (found.is_remote, found.device_type));
+        matches
+    }
+
+    /// Get a list of [`CommandHelp`] for an infrared remote type,
+    /// such as the [`Device::remote_type()`] or the values of
+    /// [`ir_types()`][Help::ir_types()].
+    ///
+    /// Please see [`command_helps()`][Help::command_helps()]
+    /// for the commands inherited by all remote types.
+    /// Because of this, the result is not empty even if the `remote_type` is unknown.
+    pub fn command_helps_by_remote_type(&self, remote_type: &str) -> Vec<&CommandHelp> {
         // Some remotes have a "DIY " prefix. Try by removing it.
-        if let Some(remote_type) = remote_type.strip_prefix("DIY ")
-            && let Some(commands) = self.commands_ir.get(remote_type)
-        {
-            return commands;
+        let remote_type = remote_type.strip_prefix("DIY ").unwrap_or(remote_type);
+        let mut command_helps: Vec<&CommandHelp> = Vec::new();
+        if remote_type != CommandScope::OTHERS_KEY {
+            if CommandScope::from_ir_key(remote_type) == CommandScope::DeviceType
+                && let Some(commands) = self.commands_ir.get(remote_type)
+            {
+                command_helps.extend(commands);
+            }
+            if let Some(commands) = self.commands_ir.get(CommandScope::ALL_APPLIANCES_KEY) {
+                command_helps.extend(commands);
+            }
+        }
+        if let Some(commands) = self.commands_ir.get(CommandScope::OTHERS_KEY) {
+            command_helps.extend(commands);
         }
-        CommandHelp::empty_vec()
+
+        let mut command_names = HashSet::new();
+        command_helps.retain(|command_help| {
+            let command = &command_help.command;
+            command_names.insert((command.command_type.as_str(), command.command.as_str()))
+        });
+        command_helps
     }
 
-    fn fmt_commands(
+    fn fmt_commands<T: Display>(
         &self,
-        commands: &HashMap<String, Vec<CommandHelp>>,
+        commands: &HashMap<String, Vec<T>>,
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
         for (device_type, helps) in commands {
@@ -155,6 +674,201 @@ impl Help {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn command_help_display() {
+        let help = CommandHelp {
+            command: CommandRequest::from("setChildLock:`0` or `1`"),
+            description: Markdown::new("`1`, enable<br>`0`, disable"),
+            parameter_schema: ParameterSchema::None,
+            scope: CommandScope::DeviceType,
+            source: HelpSource::BuiltIn,
+        };
+        assert_eq!(
+            help.to_string(),
+            "setChildLock:`0` or `1`\n    `1`, enable\n    `0`, disable"
+        );
+        assert_eq!(
+            format!("{help:#}"),
+            "setChildLock:0 or 1\n    1, enable\n    0, disable"
+        );
+        assert!(help.text(true).contains('\x1b'));
+    }
+
+    #[test]
+    fn parameter_schemas() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let command_helps: Vec<&CommandHelp> = help
+            .commands
+            .values()
+            .chain(help.commands_ir.values())
+            .flatten()
+            .collect();
+        // The commands whose parameters are free text in the documentation.
+        let texts: BTreeSet<&str> = command_helps
+            .iter()
+            .filter(|help| matches!(help.parameter_schema(), ParameterSchema::Text(_)))
+            .map(|help| help.command().command.as_str())
+            .collect();
+        assert_eq!(texts, BTreeSet::from(["customPage", "customQuote"]));
+        Ok(())
+    }
+
+    #[test]
+    fn status_helps() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let helps = help.status_helps_by_device_type("MeterPlus");
+        let battery = helps.iter().find(|help| help.key() == "battery").unwrap();
+        assert_eq!(battery.value_type(), "Integer");
+        assert_eq!(battery.unit(), "%");
+        assert_eq!(battery.range(), "0-100");
+        assert_eq!(battery.summary(), "Integer, %, 0-100");
+        assert!(help.status_helps_by_device_type("Hub 2").len() > 1);
+        assert!(help.status_helps_by_device_type("Unknown").is_empty());
+        Ok(())
+    }
+
+    fn command_names(command_helps: &[&CommandHelp]) -> Vec<(String, CommandScope)> {
+        command_helps
+            .iter()
+            .map(|help| (help.command().command.clone(), help.scope()))
+            .collect()
+    }
+
+    #[test]
+    fn command_helps_by_remote_type() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let button = "{user-defined button name}".to_string();
+        let light = command_names(&help.command_helps_by_remote_type("Light"));
+        assert_eq!(
+            light,
+            vec![
+                ("brightnessUp".into(), CommandScope::DeviceType),
+                ("brightnessDown".into(), CommandScope::DeviceType),
+                ("turnOn".into(), CommandScope::AllAppliances),
+                ("turnOff".into(), CommandScope::AllAppliances),
+                (button.clone(), CommandScope::AllAppliances),
+            ]
+        );
+        assert_eq!(
+            command_names(&help.command_helps_by_remote_type("DIY Light")),
+            light
+        );
+
+        // Unknown types still have the inherited commands.
+        let unknown = help.command_helps_by_remote_type("DIY Projector");
+        assert_eq!(unknown.len(), 3);
+
+        let others = command_names(&help.command_helps_by_remote_type("Others"));
+        assert_eq!(others, vec![(button, CommandScope::Others)]);
+        Ok(())
+    }
+
+    fn matched_device_types<'a>(matches: &[CommandMatch<'a>]) -> Vec<&'a str> {
+        matches.iter().map(|found| found.device_type).collect()
+    }
+
+    #[test]
+    fn find_command() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let matches = help.find_command("setColorTemperature");
+        let device_types = matched_device_types(&matches);
+        assert!(device_types.contains(&"Color Bulb"));
+        assert!(device_types.is_sorted());
+        assert!(matches.iter().all(|found| !found.is_remote));
+
+        assert!(help.find_command("setcolortemperature").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn search() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let matches = help.search("SWING");
+        assert_eq!(matches.last().unwrap().device_type, "Fan");
+        assert!(matches.last().unwrap().is_remote);
+        assert_eq!(matches.last().unwrap().to_string(), "Fan (infrared remote)");
+        Ok(())
+    }
+
+    #[test]
+    fn device_types() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let device_types = help.device_types();
+        assert!(device_types.is_sorted());
+        assert!(device_types.contains(&"Bot"));
+
+        let ir_types = help.ir_types();
+        assert!(ir_types.is_sorted());
+        assert!(ir_types.contains(&"TV"));
+        assert!(ir_types.contains(&"Others"));
+        assert!(!ir_types.contains(&CommandScope::ALL_APPLIANCES_KEY));
+        Ok(())
+    }
+
+    #[test]
+    fn version() {
+        let from_version = |version: u32| {
+            Help::from_json_str(&format!(r#"{{"version":{version}}}"#), HelpSource::BuiltIn)
+        };
+        assert!(from_version(1).is_ok());
+        assert!(from_version(0).is_err());
+        assert!(from_version(Help::SCHEMA_VERSION + 1).is_err());
+        assert!(Help::from_json_str("{}", HelpSource::BuiltIn).is_err());
+    }
+
+    #[test]
+    fn merge() -> anyhow::Result<()> {
+        let mut help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let num_bot_commands = help.command_helps_by_device_type("Bot").len();
+        let source = HelpSource::Path("override.json".into());
+        let other = Help::from_json_str(
+            r#"{
+                "version": 1,
+                "commands": {
+                    "Bot": [
+                        {"command": {"command": "turnOn", "parameter": "default", "commandType": "command"}, "description": "override"},
+                        {"command": {"command": "newCommand", "parameter": "default", "commandType": "command"}, "description": "new"}
+                    ],
+                    "New Device": [
+                        {"command": {"command": "turnOn", "parameter": "default", "commandType": "command"}, "description": "new device"}
+                    ]
+                },
+                "device_type_aliases": {"MeterPlus": ["Bot"]}
+            }"#,
+            source.clone(),
+        )?;
+        help.merge(other);
+
+        let bot = help.command_helps_by_device_type("Bot");
+        assert_eq!(bot.len(), num_bot_commands + 1);
+        let turn_on = bot
+            .iter()
+            .find(|help| help.command().command == "turnOn")
+            .unwrap();
+        assert_eq!(turn_on.description().markdown(), "override");
+        assert_eq!(turn_on.source(), &source);
+        let turn_off = bot
+            .iter()
+            .find(|help| help.command().command == "turnOff")
+            .unwrap();
+        assert_eq!(turn_off.source(), &HelpSource::BuiltIn);
+        assert_eq!(bot.last().unwrap().command().command, "newCommand");
+
+        assert_eq!(help.command_helps_by_device_type("New Device").len(), 1);
+        assert_eq!(help.device_type_aliases["MeterPlus"], ["Bot", "Meter Plus"]);
+        Ok(())
+    }
+
+    #[test]
+    fn to_json_string() -> anyhow::Result<()> {
+        let help = Help::from_json_str(include_str!("help_data.json"), HelpSource::BuiltIn)?;
+        let json = help.to_json_string()?;
+        let loaded = Help::from_json_str(&json, HelpSource::BuiltIn)?;
+        assert_eq!(loaded.to_json_string()?, json);
+        Ok(())
+    }
 
     #[test]
     fn multiple_aliases() {
@@ -164,6 +878,9 @@ mod tests {
             vec![CommandHelp {
                 command: CommandRequest::default(),
                 description: Markdown::new("test"),
+                parameter_schema: ParameterSchema::None,
+                scope: CommandScope::DeviceType,
+                source: HelpSource::BuiltIn,
             }],
         );
         help.add_device_type_alias("AliasType".into(), "NonExistentDevice".into());
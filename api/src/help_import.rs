@@ -0,0 +1,426 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use serde_json::{Value, json};
+
+use crate::{CommandScope, Help};
+
+/// Imports the Markdown documents of the [SwitchBot API]
+/// into the same JSON structure as the built-in `help_data.json`.
+///
+/// This is the Rust port of `data/generate.py`.
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+#[derive(Debug, Default)]
+pub(crate) struct HelpImporter {
+    commands: HashMap<String, Vec<Value>>,
+    commands_ir: HashMap<String, Vec<Value>>,
+    status: HashMap<String, Vec<Value>>,
+    device_type_aliases: HashMap<String, Vec<String>>,
+}
+
+impl HelpImporter {
+    const IR_FILE_NAME: &str = "virtual-infrared-remote-devices.md";
+
+    /// Import from the `devices` directory of the SwitchBotAPI repository.
+    /// `dir` can also be the root of the repository.
+    pub fn import_dir(dir: &Path) -> anyhow::Result<Value> {
+        let mut devices_dir = dir.join("devices");
+        if !devices_dir.is_dir() {
+            devices_dir = dir.to_path_buf();
+        }
+        let mut paths = Vec::new();
+        Self::find_markdown_files(&devices_dir, &mut paths)?;
+        paths.sort();
+
+        let mut importer = Self::default();
+        for path in paths {
+            let markdown = fs::read_to_string(&path)?;
+            let is_ir = path
+                .file_name()
+                .is_some_and(|name| name == Self::IR_FILE_NAME);
+            let doc = MarkdownDoc::parse(&markdown, is_ir);
+            if doc.device_name.is_empty() {
+                continue;
+            }
+            log::debug!("help import: {path:?} -> {:?}", doc.device_name);
+            importer.add(doc);
+        }
+        if importer.commands.is_empty() && importer.commands_ir.is_empty() {
+            anyhow::bail!("{}: No device documents found", dir.display());
+        }
+
+        // Webhook events report "Standing Fan",
+        // but the control commands are under "Standing Circulator Fan".
+        importer.add_device_type_alias("Standing Fan", "Standing Circulator Fan");
+        Ok(importer.into_json())
+    }
+
+    fn find_markdown_files(dir: &Path, paths: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                Self::find_markdown_files(&path, paths)?;
+            } else if path.extension().is_some_and(|ext| ext == "md") {
+                paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, doc: MarkdownDoc) {
+        for alias in &doc.aliases {
+            self.add_device_type_alias(alias, &doc.device_name);
+        }
+        if !doc.status.is_empty() {
+            self.status
+                .entry(doc.device_name)
+                .or_default()
+                .extend(doc.status);
+        }
+        for (device_type, command) in doc.commands {
+            self.commands.entry(device_type).or_default().push(command);
+        }
+        for (device_type, command) in doc.commands_ir {
+            if device_type == CommandScope::ALL_APPLIANCES_KEY
+                || device_type == CommandScope::OTHERS_KEY
+            {
+                self.commands_ir
+                    .entry(device_type)
+                    .or_default()
+                    .push(command);
+                continue;
+            }
+            for name in device_type.split(',') {
+                self.commands_ir
+                    .entry(name.trim().to_string())
+                    .or_default()
+                    .push(command.clone());
+            }
+        }
+    }
+
+    fn add_device_type_alias(&mut self, alias: &str, target: &str) {
+        let targets = self.device_type_aliases.entry(alias.into()).or_default();
+        if !targets.iter().any(|t| t == target) {
+            targets.push(target.into());
+        }
+    }
+
+    fn into_json(self) -> Value {
+        json!({
+            "version": Help::SCHEMA_VERSION,
+            "commands": self.commands,
+            "commands_ir": self.commands_ir,
+            "status": self.status,
+            "device_type_aliases": self.device_type_aliases,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Section {
+    #[default]
+    None,
+    DeviceListInfo,
+    DeviceStatus,
+    ControlCommands,
+}
+
+/// The help data parsed from a Markdown document of a device.
+#[derive(Debug, Default)]
+struct MarkdownDoc {
+    device_name: String,
+    aliases: Vec<String>,
+    commands: Vec<(String, Value)>,
+    commands_ir: Vec<(String, Value)>,
+    status: Vec<Value>,
+}
+
+impl MarkdownDoc {
+    /// Keys in the status response that are not the status of the device.
+    const STATUS_KEYS_TO_SKIP: [&str; 4] = ["Key", "deviceId", "deviceType", "hubDeviceId"];
+
+    fn parse(markdown: &str, is_ir: bool) -> Self {
+        let mut doc = Self::default();
+        let mut section = Section::None;
+        let mut in_command_table = false;
+        let mut current_device_type = String::new();
+        for line in markdown.lines() {
+            let line = line.trim();
+            match line {
+                "## Device List Information" => section = Section::DeviceListInfo,
+                "## Device Status" | "## Get Device Status" | "## Status" => {
+                    section = Section::DeviceStatus
+                }
+                "## Control Commands" => section = Section::ControlCommands,
+                _ if line.starts_with("## ") || line.starts_with("# ") => {
+                    if let Some(name) = line.strip_prefix("# ")
+                        && doc.device_name.is_empty()
+                    {
+                        doc.device_name = name.trim().into();
+                    }
+                    section = Section::None;
+                }
+                _ => {
+                    let Some(columns) = Self::table_columns(line) else {
+                        in_command_table = false;
+                        continue;
+                    };
+                    match section {
+                        Section::None => {}
+                        Section::DeviceListInfo => doc.parse_device_list_info(&columns),
+                        Section::DeviceStatus => doc.parse_device_status(&columns),
+                        Section::ControlCommands => {
+                            if !in_command_table {
+                                in_command_table = columns.len() >= 5 && columns[0] == "deviceType";
+                                continue;
+                            }
+                            doc.parse_control_command(&columns, &mut current_device_type, is_ir);
+                        }
+                    }
+                    continue;
+                }
+            }
+            in_command_table = false;
+        }
+        doc
+    }
+
+    fn table_columns(line: &str) -> Option<Vec<&str>> {
+        if !line.starts_with('|') || !line.ends_with('|') {
+            return None;
+        }
+        Some(line.trim_matches('|').split('|').map(str::trim).collect())
+    }
+
+    fn parse_device_list_info(&mut self, columns: &[&str]) {
+        const RE_ALIAS_PAT: &str = r"[*_]([^*_]+)[*_]";
+        static RE_ALIAS: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_ALIAS_PAT).unwrap());
+
+        if columns.len() < 3 || columns[0] != "deviceType" {
+            return;
+        }
+        for captures in RE_ALIAS.captures_iter(columns[2]) {
+            let alias = captures[1].trim();
+            if !alias.is_empty() && alias != self.device_name {
+                self.aliases.push(alias.into());
+            }
+        }
+    }
+
+    fn parse_device_status(&mut self, columns: &[&str]) {
+        if columns.len() < 3 {
+            return;
+        }
+        let key = columns[0].trim_matches('`');
+        if key.is_empty() || key.starts_with('-') || Self::STATUS_KEYS_TO_SKIP.contains(&key) {
+            return;
+        }
+        let description = columns[2];
+        let (unit, range) = parse_unit_and_range(description);
+        self.status.push(json!({
+            "key": key,
+            "type": columns[1],
+            "unit": unit,
+            "range": range,
+            "description": description,
+        }));
+    }
+
+    fn parse_control_command(
+        &mut self,
+        columns: &[&str],
+        current_device_type: &mut String,
+        is_ir: bool,
+    ) {
+        if columns.len() < 5 || columns[0].starts_with('-') {
+            return;
+        }
+        if !columns[0].is_empty() {
+            *current_device_type = columns[0].into();
+        }
+        let command = columns[2];
+        if current_device_type.is_empty() || command.is_empty() {
+            return;
+        }
+
+        let command_help = json!({
+            "command": {
+                "command": command,
+                "parameter": columns[3],
+                "commandType": columns[1].trim_matches('`'),
+            },
+            "description": columns[4],
+        });
+        let list = if is_ir {
+            &mut self.commands_ir
+        } else {
+            &mut self.commands
+        };
+        list.push((current_device_type.clone(), command_help));
+    }
+}
+
+/// Extract the unit and the range of values from the description text.
+fn parse_unit_and_range(description: &str) -> (&'static str, String) {
+    const UNITS: [(&str, &str); 11] = [
+        (r"\bcelsius\b", "°C"),
+        (r"\bfahrenheit\b", "°F"),
+        (r"\bppm\b", "ppm"),
+        (r"\bkelvin\b", "K"),
+        (r"\bwatts?\b", "W"),
+        (r"\bvolts?\b", "V"),
+        (r"\bmA\b", "mA"),
+        (r"\bamps?\b", "A"),
+        (r"\bminutes?\b", "min"),
+        (r"\bseconds?\b", "s"),
+        (r"\bpercent(age)?\b|%|\bbattery level\b", "%"),
+    ];
+    static RE_UNITS: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+        UNITS
+            .iter()
+            .map(|(pattern, unit)| (Regex::new(&format!("(?i){pattern}")).unwrap(), *unit))
+            .collect()
+    });
+    const RE_RANGE_PAT: &str = r"(-?\d+(?:\.\d+)?)\s*(?:-|~|to)\s*(-?\d+(?:\.\d+)?)";
+    static RE_RANGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_RANGE_PAT).unwrap());
+
+    let unit = RE_UNITS
+        .iter()
+        .find(|(re, _)| re.is_match(description))
+        .map_or("", |(_, unit)| unit);
+    let range = RE_RANGE
+        .captures(description)
+        .map_or_else(String::new, |captures| {
+            format!("{}-{}", &captures[1], &captures[2])
+        });
+    (unit, range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        let doc = MarkdownDoc::parse(
+            r#"# Test Device
+
+## Device List Information
+
+| Key        | Value Type | Description |
+| ---------- | ---------- | ----------- |
+| deviceType | String     | device type. _TestAlias_ |
+
+## Control Commands
+
+Here is some text before the table that should not break parsing.
+
+| deviceType  | commandType | Command      | command parameter | Description |
+| ----------- | ----------- | ------------ | ----------------- | ----------- |
+| Test Device | command     | turnOn       | default           | Turn on |
+|             | command     | setMode      | 1                 | Set mode |
+|             | `customize` | specialCmd   | param1            | Special command |
+|             | command     |              |                   | Ignored |
+"#,
+            false,
+        );
+        assert_eq!(doc.device_name, "Test Device");
+        assert_eq!(doc.aliases, ["TestAlias"]);
+        assert!(doc.commands_ir.is_empty());
+        assert_eq!(doc.commands.len(), 3);
+        assert_eq!(doc.commands[0].0, "Test Device");
+        assert_eq!(
+            doc.commands[2].1,
+            json!({
+                "command": {
+                    "command": "specialCmd",
+                    "parameter": "param1",
+                    "commandType": "customize",
+                },
+                "description": "Special command",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_status() {
+        let doc = MarkdownDoc::parse(
+            r#"# Test Meter
+
+## Device Status
+
+| Key         | Value Type | Description |
+| ----------- | ---------- | ----------- |
+| deviceId    | String     | device ID |
+| temperature | Float      | temperature in celsius |
+| battery     | Integer    | the current battery level, 0-100 |
+"#,
+            false,
+        );
+        assert_eq!(
+            doc.status,
+            [
+                json!({
+                    "key": "temperature",
+                    "type": "Float",
+                    "unit": "°C",
+                    "range": "",
+                    "description": "temperature in celsius",
+                }),
+                json!({
+                    "key": "battery",
+                    "type": "Integer",
+                    "unit": "%",
+                    "range": "0-100",
+                    "description": "the current battery level, 0-100",
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_ir_commands() {
+        let doc = MarkdownDoc::parse(
+            r#"# Virtual infrared remote devices
+
+## Control Commands
+
+| deviceType | commandType | Command | command parameter | Description |
+| ---------- | ----------- | ------- | ----------------- | ----------- |
+| TV, IPTV   | command     | SetChannel | {channel number} | set the TV channel |
+| Others     | `customize` | {user-defined button name} | default | all user-defined buttons |
+"#,
+            true,
+        );
+        let mut importer = HelpImporter::default();
+        importer.add(doc);
+        assert!(importer.commands.is_empty());
+        let mut keys: Vec<_> = importer.commands_ir.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["IPTV", "Others", "TV"]);
+    }
+
+    #[test]
+    fn unit_and_range() {
+        assert_eq!(
+            parse_unit_and_range("temperature in celsius"),
+            ("°C", "".into())
+        );
+        assert_eq!(
+            parse_unit_and_range("humidity percentage, 0-100"),
+            ("%", "0-100".into())
+        );
+        assert_eq!(
+            parse_unit_and_range("the CO2 ppm, 0 to 9999"),
+            ("ppm", "0-9999".into())
+        );
+        assert_eq!(parse_unit_and_range("firmware version"), ("", "".into()));
+    }
+}
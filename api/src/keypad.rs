@@ -0,0 +1,580 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use super::*;
+
+/// The type of a [`Passcode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PasscodeType {
+    /// Valid until deleted.
+    #[default]
+    Permanent,
+    /// Valid only in the [`TimeWindow`].
+    TimeLimit,
+    /// Valid only once in the [`TimeWindow`].
+    Disposable,
+    /// The emergency passcode.
+    Urgent,
+}
+
+impl PasscodeType {
+    const NAMES: [(&str, PasscodeType); 4] = [
+        ("permanent", Self::Permanent),
+        ("timeLimit", Self::TimeLimit),
+        ("disposable", Self::Disposable),
+        ("urgent", Self::Urgent),
+    ];
+
+    /// True if this type requires a [`TimeWindow`].
+    pub fn requires_time_window(self) -> bool {
+        matches!(self, Self::TimeLimit | Self::Disposable)
+    }
+}
+
+impl FromStr for PasscodeType {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|(_, passcode_type)| *passcode_type)
+            .ok_or_else(|| anyhow::anyhow!(r#"Unknown passcode type "{value}""#))
+    }
+}
+
+impl Display for PasscodeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, _) = Self::NAMES.iter().find(|(_, t)| t == self).unwrap();
+        write!(f, "{name}")
+    }
+}
+
+/// The time window in which a [`Passcode`] is valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    start: SystemTime,
+    end: SystemTime,
+}
+
+impl TimeWindow {
+    /// Construct from the `start` and the `end` time.
+    /// The `end` must be later than the `start`.
+    pub fn new(start: SystemTime, end: SystemTime) -> anyhow::Result<Self> {
+        if end <= start {
+            anyhow::bail!("The end time must be later than the start time");
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Construct a window from the `start` for the `duration`.
+    /// Fails if the `duration` is zero,
+    /// or if the end time is out of the range of [`SystemTime`].
+    pub fn starting_at(start: SystemTime, duration: Duration) -> anyhow::Result<Self> {
+        if duration.is_zero() {
+            anyhow::bail!("The duration of the time window must not be zero");
+        }
+        let Some(end) = start.checked_add(duration) else {
+            anyhow::bail!("The time window is too long: {duration:?}");
+        };
+        Ok(Self { start, end })
+    }
+
+    /// Construct a window from now for the `duration`.
+    pub fn from_now(duration: Duration) -> anyhow::Result<Self> {
+        Self::starting_at(SystemTime::now(), duration)
+    }
+
+    /// The start time.
+    pub fn start(&self) -> SystemTime {
+        self.start
+    }
+
+    /// The end time.
+    pub fn end(&self) -> SystemTime {
+        self.end
+    }
+
+    /// The seconds since the Unix epoch, as the SwitchBot API requires.
+    fn unix_seconds(time: SystemTime) -> u64 {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// A passcode of keypads.
+/// Please see [`Keypad::create_key()`].
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use switchbot_api::{Passcode, TimeWindow};
+/// let window = TimeWindow::from_now(Duration::from_secs(2 * 60 * 60))?;
+/// let passcode = Passcode::time_limit("guest", "123456", window);
+/// assert!(passcode.validate().is_ok());
+/// assert!(Passcode::permanent("guest", "12ab").validate().is_err());
+/// # anyhow::Ok(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passcode {
+    name: String,
+    passcode_type: PasscodeType,
+    password: SecretString,
+    time_window: Option<TimeWindow>,
+}
+
+impl Passcode {
+    const MIN_PASSWORD_LEN: usize = 6;
+    const MAX_PASSWORD_LEN: usize = 12;
+
+    fn new(
+        name: &str,
+        passcode_type: PasscodeType,
+        password: &str,
+        time_window: Option<TimeWindow>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            passcode_type,
+            password: password.into(),
+            time_window,
+        }
+    }
+
+    /// A passcode that is valid until deleted.
+    pub fn permanent(name: &str, password: &str) -> Self {
+        Self::new(name, PasscodeType::Permanent, password, None)
+    }
+
+    /// A passcode that is valid only in the `time_window`.
+    pub fn time_limit(name: &str, password: &str, time_window: TimeWindow) -> Self {
+        Self::new(name, PasscodeType::TimeLimit, password, Some(time_window))
+    }
+
+    /// A passcode that is valid only once in the `time_window`.
+    pub fn disposable(name: &str, password: &str, time_window: TimeWindow) -> Self {
+        Self::new(name, PasscodeType::Disposable, password, Some(time_window))
+    }
+
+    /// An emergency passcode.
+    pub fn urgent(name: &str, password: &str) -> Self {
+        Self::new(name, PasscodeType::Urgent, password, None)
+    }
+
+    /// The name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The [`PasscodeType`].
+    pub fn passcode_type(&self) -> PasscodeType {
+        self.passcode_type
+    }
+
+    /// The [`TimeWindow`], if any.
+    pub fn time_window(&self) -> Option<&TimeWindow> {
+        self.time_window.as_ref()
+    }
+
+    /// Check if the SwitchBot API can accept this passcode.
+    ///
+    /// The password must be 6 to 12 digits,
+    /// and [`PasscodeType::TimeLimit`] and [`PasscodeType::Disposable`]
+    /// must have a [`TimeWindow`] that is not over.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.name.trim().is_empty() {
+            anyhow::bail!("The passcode name must not be empty");
+        }
+        let password = self.password.expose();
+        if !(Self::MIN_PASSWORD_LEN..=Self::MAX_PASSWORD_LEN).contains(&password.len())
+            || !password.bytes().all(|b| b.is_ascii_digit())
+        {
+            anyhow::bail!(
+                "The password must be {} to {} digits",
+                Self::MIN_PASSWORD_LEN,
+                Self::MAX_PASSWORD_LEN
+            );
+        }
+        match (&self.time_window, self.passcode_type.requires_time_window()) {
+            (None, true) => anyhow::bail!(
+                "The passcode type {} requires a time window",
+                self.passcode_type
+            ),
+            (Some(time_window), true) if time_window.end <= SystemTime::now() => {
+                anyhow::bail!("The time window is already over")
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The `createKey` [`CommandRequest`] for this passcode.
+    pub fn to_command(&self) -> anyhow::Result<CommandRequest> {
+        self.validate()?;
+        let mut parameter = serde_json::json!({
+            "name": self.name,
+            "type": self.passcode_type,
+            "password": self.password.expose(),
+        });
+        if let Some(time_window) = &self.time_window {
+            parameter["startTime"] = TimeWindow::unix_seconds(time_window.start).into();
+            parameter["endTime"] = TimeWindow::unix_seconds(time_window.end).into();
+        }
+        Ok(CommandRequest {
+            command: Keypad::CREATE_KEY.into(),
+            parameter: parameter.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// The typed API for the Keypad devices.
+///
+/// The results of the requests arrive asynchronously through the webhook.
+/// Please see [`KeypadRequests`] to match them.
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{Device, Keypad, KeypadRequests, Passcode};
+/// # async fn add(device: &Device, requests: &mut KeypadRequests) -> anyhow::Result<()> {
+/// let keypad = Keypad::new(device)?;
+/// let request = keypad.create_key(&Passcode::permanent("me", "123456")).await?;
+/// requests.push(request);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Keypad<'a> {
+    device: &'a Device,
+}
+
+impl<'a> Keypad<'a> {
+    const CREATE_KEY: &'static str = "createKey";
+    const DELETE_KEY: &'static str = "deleteKey";
+
+    /// Construct from a [`Device`].
+    /// Returns an error if the `device` is not a keypad.
+    pub fn new(device: &'a Device) -> anyhow::Result<Self> {
+        if !Self::is_keypad(device) {
+            anyhow::bail!("{device} is not a keypad");
+        }
+        Ok(Self { device })
+    }
+
+    /// True if the `device` is a keypad,
+    /// such as "Keypad" or "Keypad Touch".
+    pub fn is_keypad(device: &Device) -> bool {
+        device.device_type().starts_with("Keypad")
+    }
+
+    /// True if the `command` is a keypad command
+    /// whose parameter is sent as a JSON object.
+    pub(crate) fn has_object_parameter(command: &CommandRequest) -> bool {
+        (command.command == Self::CREATE_KEY || command.command == Self::DELETE_KEY)
+            && matches!(command.command_type.as_str(), "" | "command")
+    }
+
+    /// Send the `createKey` command.
+    pub async fn create_key(&self, passcode: &Passcode) -> anyhow::Result<KeypadRequest> {
+        let response = self.device.command(&passcode.to_command()?).await?;
+        Ok(KeypadRequest::new(
+            self.device,
+            Self::CREATE_KEY,
+            passcode.name(),
+            &response,
+        ))
+    }
+
+    /// Send the `deleteKey` command.
+    pub async fn delete_key(&self, id: u64) -> anyhow::Result<KeypadRequest> {
+        let command = CommandRequest {
+            command: Self::DELETE_KEY.into(),
+            parameter: serde_json::json!({ "id": id }).to_string(),
+            ..Default::default()
+        };
+        let response = self.device.command(&command).await?;
+        Ok(KeypadRequest::new(
+            self.device,
+            Self::DELETE_KEY,
+            &id.to_string(),
+            &response,
+        ))
+    }
+}
+
+/// A request by [`Keypad`] waiting for its result from the webhook.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeypadRequest {
+    device_id: String,
+    event_name: String,
+    target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command_id: Option<String>,
+    requested_at: u64,
+}
+
+impl KeypadRequest {
+    fn new(device: &Device, event_name: &str, target: &str, response: &CommandResponse) -> Self {
+        Self {
+            device_id: device.device_id().into(),
+            event_name: event_name.into(),
+            target: target.into(),
+            command_id: response.command_id().map(Into::into),
+            requested_at: TimeWindow::unix_seconds(SystemTime::now()),
+        }
+    }
+
+    /// The ID of the keypad device.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// `createKey` or `deleteKey`.
+    pub fn event_name(&self) -> &str {
+        &self.event_name
+    }
+
+    /// The passcode name for `createKey`, or the passcode ID for `deleteKey`.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The time the request was sent.
+    pub fn requested_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.requested_at)
+    }
+
+    fn matches_command_id(&self, context: &serde_json::Value) -> bool {
+        self.command_id.as_deref().is_some_and(|command_id| {
+            context.get("commandId").and_then(serde_json::Value::as_str) == Some(command_id)
+        })
+    }
+
+    fn matches_device_and_event(&self, context: &serde_json::Value) -> bool {
+        let field = |name: &str| context.get(name).and_then(serde_json::Value::as_str);
+        field("eventName") == Some(self.event_name.as_str())
+            && field("deviceMac").is_some_and(|mac| mac.eq_ignore_ascii_case(&self.device_id))
+    }
+}
+
+impl Display for KeypadRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            self.event_name, self.target, self.device_id
+        )
+    }
+}
+
+/// The result of a [`KeypadRequest`] from the webhook.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeypadResult {
+    /// The request.
+    pub request: KeypadRequest,
+    /// The `result` of the webhook event, such as `success`, `failed`, or `timeout`.
+    pub result: String,
+}
+
+impl KeypadResult {
+    /// True if the request succeeded.
+    pub fn is_success(&self) -> bool {
+        self.result == "success"
+    }
+}
+
+/// Pending [`KeypadRequest`]s, to match their results from the webhook.
+///
+/// When a webhook receiver is configured,
+/// pass the webhook payloads to [`handle_webhook()`][KeypadRequests::handle_webhook()].
+/// Requests are matched by the `commandId` if available,
+/// or by the device and the event name in the order of the requests.
+///
+/// This can be serialized to keep the pending requests across processes.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct KeypadRequests {
+    pending: Vec<KeypadRequest>,
+}
+
+impl KeypadRequests {
+    /// Add a pending request.
+    pub fn push(&mut self, request: KeypadRequest) {
+        self.pending.push(request);
+    }
+
+    /// The pending requests, in the order of the requests.
+    pub fn pending(&self) -> &[KeypadRequest] {
+        &self.pending
+    }
+
+    /// Remove the requests older than `max_age`,
+    /// such as when no webhook receivers are configured.
+    pub fn remove_older_than(&mut self, max_age: Duration) {
+        let now = SystemTime::now();
+        self.pending.retain(|request| {
+            now.duration_since(request.requested_at())
+                .is_ok_and(|age| age <= max_age)
+        });
+    }
+
+    /// Match a webhook payload to a pending request.
+    /// Returns the [`KeypadResult`] and removes the request if matched,
+    /// or `None` if the payload isn't for any pending requests.
+    pub fn handle_webhook(&mut self, payload: &serde_json::Value) -> Option<KeypadResult> {
+        let context = payload.get("context")?;
+        let result = context.get("result")?.as_str()?;
+        let index = self
+            .pending
+            .iter()
+            .position(|request| request.matches_command_id(context))
+            .or_else(|| {
+                self.pending.iter().position(|request| {
+                    request.command_id.is_none() && request.matches_device_and_event(context)
+                })
+            })?;
+        Some(KeypadResult {
+            request: self.pending.remove(index),
+            result: result.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn passcode_type() -> anyhow::Result<()> {
+        for name in ["permanent", "timeLimit", "disposable", "urgent"] {
+            let passcode_type: PasscodeType = name.parse()?;
+            assert_eq!(passcode_type.to_string(), name);
+            assert_eq!(serde_json::to_value(passcode_type)?, json!(name));
+        }
+        assert!("unknown".parse::<PasscodeType>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate() -> anyhow::Result<()> {
+        let window = TimeWindow::from_now(Duration::from_secs(60))?;
+        assert!(Passcode::permanent("a", "123456").validate().is_ok());
+        assert!(Passcode::permanent("a", "123456789012").validate().is_ok());
+        assert!(Passcode::permanent("a", "12345").validate().is_err());
+        assert!(
+            Passcode::permanent("a", "1234567890123")
+                .validate()
+                .is_err()
+        );
+        assert!(Passcode::permanent("a", "12345a").validate().is_err());
+        assert!(Passcode::permanent("", "123456").validate().is_err());
+        assert!(
+            Passcode::time_limit("a", "123456", window)
+                .validate()
+                .is_ok()
+        );
+        let past = TimeWindow::starting_at(SystemTime::UNIX_EPOCH, Duration::from_secs(60))?;
+        assert!(
+            Passcode::disposable("a", "123456", past)
+                .validate()
+                .is_err()
+        );
+        let no_window = Passcode::new("a", PasscodeType::TimeLimit, "123456", None);
+        assert!(no_window.validate().is_err());
+        assert!(TimeWindow::new(window.end(), window.start()).is_err());
+        assert!(TimeWindow::from_now(Duration::MAX).is_err());
+        assert!(TimeWindow::from_now(Duration::ZERO).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn to_command() -> anyhow::Result<()> {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000);
+        let window = TimeWindow::starting_at(start, Duration::from_secs(3600))?;
+        let command = Passcode::time_limit("guest", "123456", window).to_command()?;
+        assert_eq!(command.command, "createKey");
+        let parameter: serde_json::Value = serde_json::from_str(&command.parameter)?;
+        assert_eq!(
+            parameter,
+            json!({
+                "name": "guest",
+                "type": "timeLimit",
+                "password": "123456",
+                "startTime": 2_000_000_000,
+                "endTime": 2_000_003_600,
+            })
+        );
+        Ok(())
+    }
+
+    fn request(event_name: &str, target: &str, command_id: Option<&str>) -> KeypadRequest {
+        KeypadRequest {
+            device_id: "AABBCCDDEEFF".into(),
+            event_name: event_name.into(),
+            target: target.into(),
+            command_id: command_id.map(Into::into),
+            requested_at: TimeWindow::unix_seconds(SystemTime::now()),
+        }
+    }
+
+    fn webhook(event_name: &str, command_id: &str, result: &str) -> serde_json::Value {
+        json!({
+            "eventType": "changeReport",
+            "eventVersion": "1",
+            "context": {
+                "deviceType": "WoKeypad",
+                "deviceMac": "AABBCCDDEEFF",
+                "eventName": event_name,
+                "commandId": command_id,
+                "result": result,
+            }
+        })
+    }
+
+    #[test]
+    fn handle_webhook() {
+        let mut requests = KeypadRequests::default();
+        requests.push(request("createKey", "a", None));
+        requests.push(request("createKey", "b", Some("CMD2")));
+        requests.push(request("deleteKey", "3", None));
+
+        // Matched by the `commandId`.
+        let result = requests
+            .handle_webhook(&webhook("createKey", "CMD2", "success"))
+            .unwrap();
+        assert_eq!(result.request.target(), "b");
+        assert!(result.is_success());
+
+        // Matched by the device and the event name.
+        let result = requests
+            .handle_webhook(&webhook("deleteKey", "CMD3", "failed"))
+            .unwrap();
+        assert_eq!(result.request.target(), "3");
+        assert!(!result.is_success());
+
+        assert!(
+            requests
+                .handle_webhook(&webhook("deleteKey", "CMD4", "success"))
+                .is_none()
+        );
+        assert_eq!(requests.pending().len(), 1);
+    }
+
+    #[test]
+    fn remove_older_than() {
+        let mut requests = KeypadRequests::default();
+        let mut old = request("createKey", "a", None);
+        old.requested_at -= 3600;
+        requests.push(old);
+        requests.push(request("createKey", "b", None));
+        requests.remove_older_than(Duration::from_secs(60));
+        assert_eq!(requests.pending().len(), 1);
+        assert_eq!(requests.pending()[0].target(), "b");
+    }
+}
@@ -23,19 +23,49 @@
 //! # }
 //! ```
 
+mod api_stats;
+pub use api_stats::*;
+mod audit_log;
+pub use audit_log::*;
+mod batch_report;
+pub use batch_report::*;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cassette;
+pub use cassette::*;
 mod command_request;
 pub use command_request::*;
-mod conditional_expression;
-pub(crate) use conditional_expression::*;
+mod command_response;
+pub use command_response::*;
+mod condition;
+pub use condition::*;
+mod credentials;
+pub use credentials::*;
 mod device;
 pub use device::*;
 mod device_list;
 pub use device_list::*;
 mod help;
 pub use help::*;
+mod help_import;
+use help_import::*;
+mod keypad;
+pub use keypad::*;
 mod markdown;
 pub use markdown::*;
+mod parameter_schema;
+pub use parameter_schema::*;
+#[cfg(test)]
+mod scripted_transport;
+#[cfg(test)]
+use scripted_transport::*;
+mod secret_string;
+pub use secret_string::*;
+mod shadow_state;
+pub use shadow_state::*;
 mod switch_bot;
 pub use switch_bot::*;
 mod switch_bot_service;
 pub use switch_bot_service::*;
+mod transport;
+pub use transport::*;
@@ -1,16 +1,26 @@
 use std::{fmt::Display, sync::LazyLock};
 
+use pulldown_cmark::{Alignment, CowStr, Event, Options, Parser, Tag, TagEnd};
 use regex::Regex;
 
+/// Matches `<br>`, the only HTML meaningful in the SwitchBot documents.
+static RE_BR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").unwrap());
+
 /// Represents a simple Markdown.
 ///
+/// The [`Display`] trait writes the Markdown with `<br>` as newlines.
+/// The alternate form (`{:#}`) renders it as plain text;
+/// please see [`plain_text()`][Markdown::plain_text()].
+///
 /// # Examples
 /// ```
 /// # use switchbot_api::Markdown;
 /// assert_eq!(Markdown::new("a<br>b").to_string(), "a\nb");
+/// assert_eq!(Markdown::new("`0` or **1**").to_string(), "`0` or **1**");
+/// assert_eq!(format!("{:#}", Markdown::new("`0` or **1**")), "0 or 1");
 /// ```
-#[derive(Clone, Debug, Default, serde::Deserialize)]
-#[serde(from = "String")]
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(from = "String", into = "String")]
 pub struct Markdown {
     markdown: String,
 }
@@ -21,6 +31,12 @@ impl From<String> for Markdown {
     }
 }
 
+impl From<Markdown> for String {
+    fn from(markdown: Markdown) -> Self {
+        markdown.markdown
+    }
+}
+
 impl Markdown {
     pub fn new(markdown: &str) -> Self {
         Self {
@@ -33,19 +49,376 @@ impl Markdown {
         &self.markdown
     }
 
-    fn plain_text(&self) -> String {
-        const RE_BR_PAT: &str = r"(?i)<br\s*/?>";
-        static RE_BR: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_BR_PAT).unwrap());
+    /// Render as plain text without any Markdown syntax.
+    pub fn plain_text(&self) -> String {
+        self.text(false)
+    }
+
+    /// Render as text with [ANSI escape codes] for styling.
+    ///
+    /// [ANSI escape codes]: https://en.wikipedia.org/wiki/ANSI_escape_code
+    pub fn ansi_text(&self) -> String {
+        self.text(true)
+    }
+
+    /// Render as [`ansi_text()`][Markdown::ansi_text()] if `is_ansi` is `true`,
+    /// otherwise as [`plain_text()`][Markdown::plain_text()].
+    ///
+    /// Whether the output supports ANSI escape codes
+    /// is up to the caller, such as whether it's a terminal,
+    /// or the [`NO_COLOR`] environment variable.
+    ///
+    /// [`NO_COLOR`]: https://no-color.org/
+    pub fn text(&self, is_ansi: bool) -> String {
+        TextRenderer::new(is_ansi).render(&self.markdown)
+    }
+
+    /// The Markdown with `<br>` replaced with newlines.
+    fn br_to_newlines(&self) -> String {
         RE_BR.replace_all(&self.markdown, "\n").into()
     }
+
+    /// Render as HTML.
+    ///
+    /// Raw HTML in the Markdown is escaped, except `<br>`.
+    pub fn html(&self) -> String {
+        let events = Self::parser(&self.markdown).flat_map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Self::escape_html(&html),
+            event => vec![event],
+        });
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, events);
+        html
+    }
+
+    /// Split the raw `html` into `<br>` and text events,
+    /// so that the rest of the raw HTML is escaped.
+    fn escape_html(html: &str) -> Vec<Event<'static>> {
+        let mut events = Vec::new();
+        let mut last = 0;
+        for br in RE_BR.find_iter(html) {
+            if br.start() > last {
+                events.push(Event::Text(CowStr::from(
+                    html[last..br.start()].to_string(),
+                )));
+            }
+            events.push(Event::InlineHtml(CowStr::from(br.as_str().to_string())));
+            last = br.end();
+        }
+        if last < html.len() {
+            events.push(Event::Text(CowStr::from(html[last..].to_string())));
+        }
+        events
+    }
+
+    fn parser(markdown: &str) -> Parser<'_> {
+        Parser::new_ext(
+            markdown,
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+        )
+    }
 }
 
 impl Display for Markdown {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.plain_text())
+        if f.alternate() {
+            return write!(f, "{}", self.plain_text());
+        }
+        write!(f, "{}", self.br_to_newlines())
     }
 }
 
+/// Renders Markdown as text, optionally with ANSI escape codes.
+#[derive(Debug, Default)]
+struct TextRenderer {
+    is_ansi: bool,
+    text: String,
+    is_line_start: bool,
+    indent: String,
+    indent_lengths: Vec<usize>,
+    lists: Vec<Option<u64>>,
+    is_item_start: bool,
+    links: Vec<String>,
+    table: Option<Table>,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    num_header_rows: usize,
+    cell: Option<String>,
+}
+
+impl TextRenderer {
+    const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+    const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+    const UNDERLINE: (&str, &str) = ("\x1b[4m", "\x1b[24m");
+    const STRIKETHROUGH: (&str, &str) = ("\x1b[9m", "\x1b[29m");
+    const CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+    const DIM: (&str, &str) = ("\x1b[2m", "\x1b[22m");
+
+    fn new(is_ansi: bool) -> Self {
+        Self {
+            is_ansi,
+            is_line_start: true,
+            ..Default::default()
+        }
+    }
+
+    fn render(mut self, markdown: &str) -> String {
+        for event in Markdown::parser(markdown) {
+            self.event(event);
+        }
+        self.text
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.write(&text),
+            Event::Code(code) => self.write_styled(Self::CODE, &code),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for _ in RE_BR.find_iter(&html) {
+                    self.write("\n");
+                }
+            }
+            Event::SoftBreak => self.write(" "),
+            Event::HardBreak | Event::Rule => self.write("\n"),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph | Tag::Heading { .. } | Tag::BlockQuote(_) | Tag::CodeBlock(_) => {
+                self.start_block();
+                if matches!(tag, Tag::Heading { .. }) {
+                    self.write_style(Self::BOLD.0);
+                } else if matches!(tag, Tag::CodeBlock(_) | Tag::BlockQuote(_)) {
+                    self.push_indent("    ");
+                }
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.ensure_new_line();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.ensure_new_line();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => (if self.is_ansi { "• " } else { "- " }).to_string(),
+                };
+                self.write(&marker);
+                self.push_indent(&" ".repeat(marker.chars().count()));
+                self.is_item_start = true;
+            }
+            Tag::Emphasis => self.write_style(Self::ITALIC.0),
+            Tag::Strong => self.write_style(Self::BOLD.0),
+            Tag::Strikethrough => self.write_style(Self::STRIKETHROUGH.0),
+            Tag::Link { dest_url, .. } => {
+                self.write_style(Self::UNDERLINE.0);
+                self.links.push(dest_url.into_string());
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table {
+                    alignments,
+                    ..Default::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = &mut self.table {
+                    table.cell = Some(String::new());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => self.write_style(Self::BOLD.1),
+            TagEnd::CodeBlock | TagEnd::BlockQuote(_) => self.pop_indent(),
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::Item => self.pop_indent(),
+            TagEnd::Emphasis => self.write_style(Self::ITALIC.1),
+            TagEnd::Strong => self.write_style(Self::BOLD.1),
+            TagEnd::Strikethrough => self.write_style(Self::STRIKETHROUGH.1),
+            TagEnd::Link => {
+                self.write_style(Self::UNDERLINE.1);
+                if let Some(url) = self.links.pop()
+                    && !url.is_empty()
+                {
+                    self.write(" (");
+                    self.write_styled(Self::DIM, &url);
+                    self.write(")");
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.num_header_rows = table.rows.len();
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table
+                    && let Some(cell) = table.cell.take()
+                    && let Some(row) = table.rows.last_mut()
+                {
+                    row.push(cell);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.write_table(&table);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write_table(&mut self, table: &Table) {
+        let num_columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; num_columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(text_width(cell));
+            }
+        }
+        for (row_index, row) in table.rows.iter().enumerate() {
+            if row_index > 0 {
+                self.write("\n");
+            }
+            let is_header = row_index < table.num_header_rows;
+            if is_header {
+                self.write_style(Self::BOLD.0);
+            }
+            let mut line = String::new();
+            for (i, width) in widths.iter().enumerate() {
+                let cell = row.get(i).map_or("", String::as_str);
+                let padding = width - text_width(cell);
+                let (left, right) = match table.alignments.get(i) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(&" ".repeat(left));
+                line.push_str(cell);
+                line.push_str(&" ".repeat(right));
+            }
+            self.write(line.trim_end());
+            if is_header {
+                self.write_style(Self::BOLD.1);
+                if row_index + 1 == table.num_header_rows {
+                    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+                    let rule = rule.join("  ");
+                    self.write("\n");
+                    self.write(&rule);
+                }
+            }
+        }
+    }
+
+    /// Separate blocks by an empty line.
+    fn start_block(&mut self) {
+        if self.is_item_start {
+            self.is_item_start = false;
+            return;
+        }
+        if !self.text.is_empty() {
+            self.ensure_new_line();
+            self.text.push('\n');
+        }
+    }
+
+    fn ensure_new_line(&mut self) {
+        if !self.text.is_empty() && !self.is_line_start {
+            self.write("\n");
+        }
+    }
+
+    fn push_indent(&mut self, indent: &str) {
+        self.indent_lengths.push(indent.len());
+        self.indent.push_str(indent);
+    }
+
+    fn pop_indent(&mut self) {
+        if let Some(len) = self.indent_lengths.pop() {
+            self.indent.truncate(self.indent.len() - len);
+        }
+    }
+
+    fn write_style(&mut self, style: &str) {
+        if self.is_ansi {
+            self.write_raw(style);
+        }
+    }
+
+    fn write_styled(&mut self, style: (&str, &str), text: &str) {
+        self.write_style(style.0);
+        self.write(text);
+        self.write_style(style.1);
+    }
+
+    fn write(&mut self, text: &str) {
+        if let Some(table) = &mut self.table
+            && let Some(cell) = &mut table.cell
+        {
+            cell.push_str(text);
+            return;
+        }
+        self.is_item_start = false;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.text.push('\n');
+                self.is_line_start = true;
+            }
+            if !line.is_empty() {
+                if self.is_line_start {
+                    self.text.push_str(&self.indent);
+                    self.is_line_start = false;
+                }
+                self.text.push_str(line);
+            }
+        }
+    }
+
+    fn write_raw(&mut self, text: &str) {
+        if let Some(table) = &mut self.table
+            && let Some(cell) = &mut table.cell
+        {
+            cell.push_str(text);
+            return;
+        }
+        self.text.push_str(text);
+    }
+}
+
+/// The number of characters excluding ANSI escape codes.
+fn text_width(text: &str) -> usize {
+    const RE_ANSI_PAT: &str = r"\x1b\[[0-9;]*m";
+    static RE_ANSI: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_ANSI_PAT).unwrap());
+    RE_ANSI.replace_all(text, "").chars().count()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,6 +427,17 @@ mod tests {
         Markdown::new(markdown).plain_text()
     }
 
+    fn to_ansi_text(markdown: &str) -> String {
+        Markdown::new(markdown).ansi_text()
+    }
+
+    #[test]
+    fn display() {
+        let markdown = Markdown::new("`0` or **1**<br>*a* b");
+        assert_eq!(markdown.to_string(), "`0` or **1**\n*a* b");
+        assert_eq!(format!("{markdown:#}"), "0 or 1\na b");
+    }
+
     #[test]
     fn plain_text() {
         assert_eq!(to_plain_text(""), "");
@@ -67,4 +451,70 @@ mod tests {
 
         assert_eq!(to_plain_text("a<br>b<br>c"), "a\nb\nc");
     }
+
+    #[test]
+    fn plain_text_inline() {
+        assert_eq!(to_plain_text("`0` or `1`"), "0 or 1");
+        assert_eq!(to_plain_text("*a* **b** ~~c~~"), "a b c");
+        assert_eq!(
+            to_plain_text("see [docs](https://example.com)"),
+            "see docs (https://example.com)"
+        );
+        assert_eq!(
+            to_plain_text("mode_int, fan_level_int"),
+            "mode_int, fan_level_int"
+        );
+    }
+
+    #[test]
+    fn plain_text_blocks() {
+        assert_eq!(to_plain_text("a\n\nb"), "a\n\nb");
+        assert_eq!(to_plain_text("a\nb"), "a b");
+        assert_eq!(to_plain_text("* a\n* b"), "- a\n- b");
+        assert_eq!(to_plain_text("1. a\n1. b"), "1. a\n2. b");
+        assert_eq!(
+            to_plain_text("x\n\n* a\n  * b\n* c"),
+            "x\n\n- a\n  - b\n- c"
+        );
+    }
+
+    #[test]
+    fn plain_text_table() {
+        assert_eq!(
+            to_plain_text("| a | bb |\n|---|---:|\n| ccc | d |"),
+            "a    bb\n---  --\nccc   d"
+        );
+    }
+
+    #[test]
+    fn ansi_text() {
+        assert_eq!(to_ansi_text("`0`"), "\x1b[36m0\x1b[39m");
+        assert_eq!(to_ansi_text("**a**"), "\x1b[1ma\x1b[22m");
+        assert_eq!(to_ansi_text("* a"), "• a");
+    }
+
+    #[test]
+    fn ansi_text_table() {
+        assert_eq!(
+            to_ansi_text("| a | b |\n|---|---|\n| `ccc` | d |"),
+            "\x1b[1ma    b\x1b[22m\n---  -\n\x1b[36mccc\x1b[39m  d"
+        );
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            Markdown::new("`0` or **1**").html(),
+            "<p><code>0</code> or <strong>1</strong></p>\n"
+        );
+        assert_eq!(Markdown::new("a<br />b").html(), "<p>a<br />b</p>\n");
+        assert_eq!(
+            Markdown::new("a<b onclick=\"x\">b</b>").html(),
+            "<p>a&lt;b onclick=\"x\"&gt;b&lt;/b&gt;</p>\n"
+        );
+        assert_eq!(
+            Markdown::new("<script>x</script>").html(),
+            "&lt;script&gt;x&lt;/script&gt;"
+        );
+    }
 }
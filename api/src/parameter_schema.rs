@@ -0,0 +1,608 @@
+use std::{fmt::Display, ops::RangeInclusive, sync::LazyLock};
+
+use regex::Regex;
+
+/// The structure of the [`CommandRequest::parameter`][crate::CommandRequest::parameter],
+/// derived from the human-readable text in the documentation.
+///
+/// Please see [`CommandHelp::parameter_schema()`][crate::CommandHelp::parameter_schema()]
+/// for how to get this enum.
+///
+/// # Examples
+/// ```
+/// # use switchbot_api::ParameterSchema;
+/// let schema = ParameterSchema::parse("`0` or `1`");
+/// assert_eq!(schema, ParameterSchema::Enum(vec!["0".into(), "1".into()]));
+/// assert!(schema.validate("1").is_ok());
+/// assert!(schema.validate("2").is_err());
+///
+/// let schema = ParameterSchema::parse("`{0-100}`");
+/// assert_eq!(schema, ParameterSchema::Range(0..=100));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub enum ParameterSchema {
+    /// The command takes no parameters (`default`).
+    #[default]
+    None,
+    /// One of the listed values, such as "`0` or `1`".
+    Enum(Vec<String>),
+    /// An integer in the range, such as "`{0-100}`" or "`0~100`".
+    Range(RangeInclusive<i64>),
+    /// Any integer.
+    Integer,
+    /// Any string.
+    String,
+    /// A value described by its name, such as "`{channel number}`".
+    Placeholder(String),
+    /// A JSON object, such as "`{"mode": mode_int, "fanGear": fan_level_int}`".
+    Object(Vec<ParameterField>),
+    /// Values separated by the `separator`, such as "`{0-255}:{0-255}:{0-255}`".
+    Tuple {
+        separator: char,
+        elements: Vec<ParameterSchema>,
+    },
+    /// One of the schemas, such as "`auto` or `101` or `{0~100}`".
+    OneOf(Vec<ParameterSchema>),
+    /// Free text, or text that could not be parsed.
+    Text(String),
+}
+
+/// A field of the [`ParameterSchema::Object`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterField {
+    /// The JSON key.
+    pub name: String,
+    /// The placeholder in the documentation, such as `mode_int`.
+    /// This is the name the description refers to.
+    pub placeholder: Option<String>,
+    /// The schema of the value.
+    pub schema: ParameterSchema,
+}
+
+impl ParameterSchema {
+    /// Parse the human-readable `parameter` text in the documentation.
+    ///
+    /// This function never fails.
+    /// Text that can't be parsed is returned as [`ParameterSchema::Text`].
+    pub fn parse(parameter: &str) -> Self {
+        const RE_BR_PAT: &str = r"(?i)<br\s*/?>";
+        static RE_BR: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_BR_PAT).unwrap());
+        const RE_EXAMPLE_PAT: &str = r"(?i),?\s*\be\.?g\.?\s.*$";
+        static RE_EXAMPLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_EXAMPLE_PAT).unwrap());
+
+        let text = RE_BR.replace_all(parameter, " ");
+        let text = text.replace(['“', '”'], "\"").replace("\\_", "_");
+        let text = RE_EXAMPLE.replace(&text, "");
+        let text = text.trim().trim_end_matches(',').trim();
+        if text.is_empty() || text == "default" {
+            return Self::None;
+        }
+
+        let alternatives = Self::split_alternatives(text);
+        let mut values = Vec::new();
+        let mut schemas = Vec::new();
+        for alternative in &alternatives {
+            match Self::parse_one(alternative) {
+                Self::Enum(list) => values.extend(list),
+                schema => schemas.push(schema),
+            }
+        }
+        if !values.is_empty() {
+            schemas.insert(0, Self::Enum(values));
+        }
+        if schemas.len() == 1 {
+            return schemas.pop().unwrap();
+        }
+        Self::OneOf(schemas)
+    }
+
+    /// Split "`a` or `b`", "`a`, `b`, or `c`", and "“1”or"2"" into alternatives.
+    fn split_alternatives(text: &str) -> Vec<&str> {
+        const RE_SEP_PAT: &str = r#"^(?i)[\s,]*(or)?[\s,]*$"#;
+        static RE_SEP: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_SEP_PAT).unwrap());
+        for quote in ['`', '"'] {
+            let fields: Vec<&str> = text.split(quote).collect();
+            if fields.len() < 3 || fields.len().is_multiple_of(2) {
+                continue;
+            }
+            // The even fields are outside of quotes. They must be separators.
+            if fields
+                .iter()
+                .step_by(2)
+                .all(|separator| RE_SEP.is_match(separator))
+            {
+                return fields.into_iter().skip(1).step_by(2).collect();
+            }
+            if quote == '"' {
+                break;
+            }
+            // A quoted JSON may contain `"`. Don't try `"` if it's quoted by "`".
+            return vec![text];
+        }
+        vec![text]
+    }
+
+    fn parse_one(text: &str) -> Self {
+        const RE_RANGE_PAT: &str = r"^\{?\s*(-?\d+)\s*[-~]\s*(-?\d+)\s*\}?$";
+        static RE_RANGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_RANGE_PAT).unwrap());
+        const RE_LITERAL_PAT: &str = r"^[\w.+-]+$";
+        static RE_LITERAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(RE_LITERAL_PAT).unwrap());
+
+        let text = text.trim();
+        let text = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        if let Some(captures) = RE_RANGE.captures(text)
+            && let Ok(min) = captures[1].parse()
+            && let Ok(max) = captures[2].parse()
+        {
+            return Self::Range(min..=max);
+        }
+        if text.starts_with('{')
+            && text.contains('"')
+            && let Some(fields) = ObjectParser::new(text).parse()
+        {
+            return Self::Object(fields);
+        }
+        if let Some(schema) = Self::parse_tuple(text) {
+            return schema;
+        }
+        if let Some(name) = text.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            return Self::Placeholder(name.trim().into());
+        }
+        if RE_LITERAL.is_match(text) {
+            return Self::Enum(vec![text.into()]);
+        }
+        Self::Text(text.into())
+    }
+
+    fn parse_tuple(text: &str) -> Option<Self> {
+        if text.contains(' ') && !text.contains('{') {
+            return None;
+        }
+        for separator in [':', ';', ','] {
+            if !text.contains(separator) {
+                continue;
+            }
+            let elements = text
+                .split(separator)
+                .map(|element| match Self::parse_one(element) {
+                    // `index0,mode0,position0` are names, not enum values.
+                    Self::Enum(values) if values.len() == 1 => Self::Placeholder(values[0].clone()),
+                    schema => schema,
+                })
+                .collect();
+            return Some(Self::Tuple {
+                separator,
+                elements,
+            });
+        }
+        None
+    }
+
+    fn from_placeholder(placeholder: &str) -> Self {
+        if placeholder.ends_with("_int") || placeholder.ends_with("_long") {
+            return Self::Integer;
+        }
+        if placeholder.ends_with("_str") {
+            return Self::String;
+        }
+        Self::Placeholder(placeholder.into())
+    }
+
+    /// Check whether the `parameter` matches the schema.
+    ///
+    /// [`ParameterSchema::Placeholder`] and [`ParameterSchema::Text`]
+    /// accept any values, because their structures are unknown.
+    pub fn validate(&self, parameter: &str) -> anyhow::Result<()> {
+        match self {
+            Self::None => {
+                if !parameter.is_empty() && parameter != "default" {
+                    anyhow::bail!(r#"No parameters expected, but got "{parameter}""#);
+                }
+            }
+            Self::Enum(values) => {
+                if !values.iter().any(|value| value == parameter) {
+                    anyhow::bail!(r#""{parameter}" is not one of {self}"#);
+                }
+            }
+            Self::Range(range) => {
+                let value: i64 = parameter
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!(r#""{parameter}" is not an integer"#))?;
+                if !range.contains(&value) {
+                    anyhow::bail!(r#""{parameter}" is not in the range {self}"#);
+                }
+            }
+            Self::Integer => {
+                parameter
+                    .parse::<i64>()
+                    .map_err(|_| anyhow::anyhow!(r#""{parameter}" is not an integer"#))?;
+            }
+            Self::Object(fields) => {
+                let json: serde_json::Value = serde_json::from_str(parameter)?;
+                Self::validate_object(fields, &json)?;
+            }
+            Self::Tuple {
+                separator,
+                elements,
+            } => {
+                let values: Vec<&str> = parameter.split(*separator).collect();
+                if values.len() != elements.len() {
+                    anyhow::bail!(
+                        r#""{parameter}" should have {} values separated by "{separator}""#,
+                        elements.len()
+                    );
+                }
+                for (element, value) in elements.iter().zip(values) {
+                    element.validate(value)?;
+                }
+            }
+            Self::OneOf(schemas) => {
+                if !schemas
+                    .iter()
+                    .any(|schema| schema.validate(parameter).is_ok())
+                {
+                    anyhow::bail!(r#""{parameter}" does not match {self}"#);
+                }
+            }
+            Self::String | Self::Placeholder(_) | Self::Text(_) => {}
+        }
+        Ok(())
+    }
+
+    fn validate_object(fields: &[ParameterField], json: &serde_json::Value) -> anyhow::Result<()> {
+        let object = json
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("{json} is not a JSON object"))?;
+        for field in fields {
+            let value = object
+                .get(&field.name)
+                .ok_or_else(|| anyhow::anyhow!(r#"Missing "{}" in {json}"#, field.name))?;
+            let is_valid = match &field.schema {
+                Self::Integer => value.is_i64() || value.is_u64(),
+                Self::Range(range) => value.as_i64().is_some_and(|value| range.contains(&value)),
+                Self::String => value.is_string(),
+                Self::Object(fields) => {
+                    Self::validate_object(fields, value)?;
+                    true
+                }
+                _ => true,
+            };
+            if !is_valid {
+                anyhow::bail!(
+                    r#"The value of "{}" should be {}"#,
+                    field.name,
+                    field.schema
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ParameterSchema {
+    /// Write the schema in a compact human-readable form,
+    /// such as `0|1`, `0-100`, or `{"mode": <int>}`.
+    /// Ranges with negative numbers use `~`, such as `-5~-1`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "default"),
+            Self::Enum(values) => write!(f, "{}", values.join("|")),
+            Self::Range(range) if *range.start() < 0 || *range.end() < 0 => {
+                write!(f, "{}~{}", range.start(), range.end())
+            }
+            Self::Range(range) => write!(f, "{}-{}", range.start(), range.end()),
+            Self::Integer => write!(f, "<int>"),
+            Self::String => write!(f, "<str>"),
+            Self::Placeholder(name) => write!(f, "<{name}>"),
+            Self::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, r#""{}": {}"#, field.name, field.schema)?;
+                }
+                write!(f, "}}")
+            }
+            Self::Tuple {
+                separator,
+                elements,
+            } => {
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                Ok(())
+            }
+            Self::OneOf(schemas) => {
+                for (i, schema) in schemas.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{schema}")?;
+                }
+                Ok(())
+            }
+            Self::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// Parses JSON-like object templates such as
+/// `{"mode": mode_int, "param": {"times": 1}}`.
+struct ObjectParser<'a> {
+    text: &'a str,
+}
+
+impl<'a> ObjectParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    fn parse(&mut self) -> Option<Vec<ParameterField>> {
+        let fields = self.parse_object()?;
+        self.skip_whitespace();
+        self.text.is_empty().then_some(fields)
+    }
+
+    fn parse_object(&mut self) -> Option<Vec<ParameterField>> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                return Some(fields);
+            }
+            let name = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let (placeholder, schema) = self.parse_value()?;
+            fields.push(ParameterField {
+                name: name.into(),
+                placeholder,
+                schema,
+            });
+            self.skip_whitespace();
+            self.eat(',');
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<(Option<String>, ParameterSchema)> {
+        if self.text.starts_with('{') {
+            return Some((None, ParameterSchema::Object(self.parse_object()?)));
+        }
+        if self.text.starts_with('"') {
+            self.parse_string()?;
+            return Some((None, ParameterSchema::String));
+        }
+        let end = self.text.find([',', '}']).unwrap_or(self.text.len());
+        let word = self.text[..end].trim();
+        self.text = &self.text[end..];
+        if word.parse::<i64>().is_ok() {
+            return Some((None, ParameterSchema::Integer));
+        }
+        // "passcode _name_str" in the documentation.
+        let placeholder = word.replace(' ', "");
+        let schema = ParameterSchema::from_placeholder(&placeholder);
+        Some((Some(placeholder), schema))
+    }
+
+    fn parse_string(&mut self) -> Option<&'a str> {
+        self.expect('"')?;
+        let end = self.text.find('"')?;
+        let value = &self.text[..end];
+        self.text = &self.text[end + 1..];
+        Some(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.text = self.text.trim_start();
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if let Some(rest) = self.text.strip_prefix(ch) {
+            self.text = rest;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, ch: char) -> Option<()> {
+        self.eat(ch).then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> ParameterSchema {
+        ParameterSchema::parse(text)
+    }
+
+    fn values(values: &[&str]) -> ParameterSchema {
+        ParameterSchema::Enum(values.iter().map(|value| value.to_string()).collect())
+    }
+
+    fn field(name: &str, placeholder: Option<&str>, schema: ParameterSchema) -> ParameterField {
+        ParameterField {
+            name: name.into(),
+            placeholder: placeholder.map(|placeholder| placeholder.into()),
+            schema,
+        }
+    }
+
+    fn placeholder(name: &str) -> ParameterSchema {
+        ParameterSchema::Placeholder(name.into())
+    }
+
+    #[test]
+    fn parse_none() {
+        assert_eq!(parse("default"), ParameterSchema::None);
+        assert_eq!(parse(""), ParameterSchema::None);
+    }
+
+    #[test]
+    fn parse_enum() {
+        assert_eq!(parse("`0` or `1`"), values(&["0", "1"]));
+        assert_eq!(parse("`1` or `2` or `3`"), values(&["1", "2", "3"]));
+        assert_eq!(
+            parse("`Normal`, `Standby`, or `Sleep`"),
+            values(&["Normal", "Standby", "Sleep"])
+        );
+        assert_eq!(parse("`off`, `0`, or `1`"), values(&["off", "0", "1"]));
+        assert_eq!(parse("“1”or\"2\""), values(&["1", "2"]));
+    }
+
+    #[test]
+    fn parse_range() {
+        assert_eq!(parse("`0-100`"), ParameterSchema::Range(0..=100));
+        assert_eq!(parse("`0~100`"), ParameterSchema::Range(0..=100));
+        assert_eq!(parse("`{2700-6500}`"), ParameterSchema::Range(2700..=6500));
+        assert_eq!(
+            parse("`{1-100}` e.g. `10`"),
+            ParameterSchema::Range(1..=100)
+        );
+        assert_eq!(
+            parse("`{1-100}`, e.g. `10`"),
+            ParameterSchema::Range(1..=100)
+        );
+    }
+
+    #[test]
+    fn parse_one_of() {
+        assert_eq!(
+            parse("`auto` or `101` or<br /> `102` or `103` or `{0~100}`"),
+            ParameterSchema::OneOf(vec![
+                values(&["auto", "101", "102", "103"]),
+                ParameterSchema::Range(0..=100)
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_object() {
+        assert_eq!(
+            parse(r#"{"mode": mode_int, "fanGear": fan_level_int}"#),
+            ParameterSchema::Object(vec![
+                field("mode", Some("mode_int"), ParameterSchema::Integer),
+                field("fanGear", Some("fan_level_int"), ParameterSchema::Integer),
+            ])
+        );
+        assert_eq!(
+            parse(r#"`{"id": ""}`"#),
+            ParameterSchema::Object(vec![field("id", None, ParameterSchema::String)])
+        );
+        assert_eq!(
+            parse(r#"{ "name": passcode \_name_str, "endTime": valid_to_long }"#),
+            ParameterSchema::Object(vec![
+                field("name", Some("passcode_name_str"), ParameterSchema::String),
+                field("endTime", Some("valid_to_long"), ParameterSchema::Integer),
+            ])
+        );
+        assert_eq!(
+            parse(r#"{"action": action_str, "param": {"times": clean_cycle_int}}"#),
+            ParameterSchema::Object(vec![
+                field("action", Some("action_str"), ParameterSchema::String),
+                field(
+                    "param",
+                    None,
+                    ParameterSchema::Object(vec![field(
+                        "times",
+                        Some("clean_cycle_int"),
+                        ParameterSchema::Integer
+                    )])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_tuple() {
+        assert_eq!(
+            parse(r#"`"{0-255}:{0-255}:{0-255}"`"#),
+            ParameterSchema::Tuple {
+                separator: ':',
+                elements: vec![ParameterSchema::Range(0..=255); 3],
+            }
+        );
+        assert_eq!(
+            parse("{temperature},{mode},{fan speed},{power state}<br />e.g. `26,1,3,on`"),
+            ParameterSchema::Tuple {
+                separator: ',',
+                elements: vec![
+                    placeholder("temperature"),
+                    placeholder("mode"),
+                    placeholder("fan speed"),
+                    placeholder("power state"),
+                ],
+            }
+        );
+        assert_eq!(
+            parse("channel;mode<br>e.g `1;0`"),
+            ParameterSchema::Tuple {
+                separator: ';',
+                elements: vec![placeholder("channel"), placeholder("mode")],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_others() {
+        assert_eq!(
+            parse("{channel number}, e.g. 15"),
+            placeholder("channel number")
+        );
+        assert_eq!(
+            parse(r#"custom text, e.g. `"Oh sea, you have so much water!"`"#),
+            ParameterSchema::Text("custom text".into())
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert!(parse("default").validate("").is_ok());
+        assert!(parse("default").validate("x").is_err());
+        assert!(parse("`0-100`").validate("100").is_ok());
+        assert!(parse("`0-100`").validate("101").is_err());
+        assert!(parse("`0-100`").validate("x").is_err());
+        let one_of = parse("`auto` or `101` or `{0~100}`");
+        assert!(one_of.validate("auto").is_ok());
+        assert!(one_of.validate("50").is_ok());
+        assert!(one_of.validate("200").is_err());
+        let tuple = parse("`{0-255}:{0-255}:{0-255}`");
+        assert!(tuple.validate("255:0:0").is_ok());
+        assert!(tuple.validate("255:0").is_err());
+        assert!(tuple.validate("256:0:0").is_err());
+        let object = parse(r#"{"mode": mode_int, "name": name_str}"#);
+        assert!(object.validate(r#"{"mode": 1, "name": "a"}"#).is_ok());
+        assert!(object.validate(r#"{"mode": "1", "name": "a"}"#).is_err());
+        assert!(object.validate(r#"{"mode": 1}"#).is_err());
+        let object =
+            ParameterSchema::Object(vec![field("level", None, ParameterSchema::Range(1..=3))]);
+        assert!(object.validate(r#"{"level": 3}"#).is_ok());
+        assert!(object.validate(r#"{"level": 4}"#).is_err());
+        assert!(object.validate(r#"{"level": "1"}"#).is_err());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(parse("`0` or `1`").to_string(), "0|1");
+        assert_eq!(parse("`0~100`").to_string(), "0-100");
+        assert_eq!(
+            parse(r#"{"mode": mode_int, "id": ""}"#).to_string(),
+            r#"{"mode": <int>, "id": <str>}"#
+        );
+        assert_eq!(parse("`{0-255}:{0-255}`").to_string(), "0-255:0-255");
+        assert_eq!(parse("`-5~-1`").to_string(), "-5~-1");
+        assert_eq!(parse("`-5~5`").to_string(), "-5~5");
+        assert_eq!(parse("-5~-1"), ParameterSchema::Range(-5..=-1));
+    }
+}
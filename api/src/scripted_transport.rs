@@ -0,0 +1,57 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use super::*;
+
+/// A [`Transport`] for tests that returns the scripted responses in order,
+/// and records the requests.
+#[derive(Debug, Default)]
+pub(crate) struct ScriptedTransport {
+    responses: Mutex<VecDeque<anyhow::Result<TransportResponse>>>,
+    requests: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+impl ScriptedTransport {
+    /// Add a successful response with the `body`.
+    pub fn ok(self, body: serde_json::Value) -> Self {
+        self.response(Ok(TransportResponse::ok(serde_json::json!({
+            "statusCode": 100,
+            "message": "success",
+            "body": body,
+        }))))
+    }
+
+    /// Add a successful response of the device list.
+    /// `devices` with the `remoteType` are virtual infrared remote devices.
+    pub fn device_list(self, devices: &[serde_json::Value]) -> Self {
+        let (remotes, devices): (Vec<_>, Vec<_>) = devices
+            .iter()
+            .partition(|device| device.get("remoteType").is_some());
+        self.ok(serde_json::json!({
+            "deviceList": devices,
+            "infraredRemoteList": remotes,
+        }))
+    }
+
+    /// Add a `response`. It can be an HTTP error, or an error without responses.
+    pub fn response(self, response: anyhow::Result<TransportResponse>) -> Self {
+        self.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    /// The requests sent so far.
+    /// It is shared, so that it can be checked after moving the transport.
+    pub fn requests(&self) -> Arc<Mutex<Vec<TransportRequest>>> {
+        self.requests.clone()
+    }
+}
+
+impl Transport for ScriptedTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        self.requests.lock().unwrap().push(request);
+        let response = self.responses.lock().unwrap().pop_front();
+        Box::pin(async { response.unwrap_or_else(|| Err(anyhow::anyhow!("No more responses"))) })
+    }
+}
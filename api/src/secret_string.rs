@@ -0,0 +1,114 @@
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Display, Formatter},
+    str::FromStr,
+};
+
+/// A string that `Debug` and `Display` don't print,
+/// such as the token and the secret.
+///
+/// Use [`expose()`][SecretString::expose()] to get the string.
+/// Serialization is not redacted, so that it can be saved.
+///
+/// # Examples
+/// ```
+/// # use switchbot_api::SecretString;
+/// let secret = SecretString::from("my_secret");
+/// assert_eq!(secret.to_string(), "[REDACTED]");
+/// assert_eq!(format!("{secret:?}"), "SecretString([REDACTED])");
+/// assert_eq!(secret.expose(), "my_secret");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// The replacement text for non-empty strings.
+    pub const REDACTED: &str = "[REDACTED]";
+
+    /// The keys of JSON objects whose values are secrets.
+    const SECRET_KEYS: [&str; 1] = ["password"];
+
+    /// Get the string.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// True if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Clear the string.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Replace the values of the secret keys in the JSON,
+    /// such as the `password` of the `createKey` command,
+    /// with [`SecretString::REDACTED`].
+    /// Returns `true` if anything is redacted.
+    pub(crate) fn redact_json(value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Object(object) => {
+                let mut is_redacted = false;
+                for (key, value) in object.iter_mut() {
+                    if Self::SECRET_KEYS.contains(&key.as_str()) {
+                        *value = Self::REDACTED.into();
+                        is_redacted = true;
+                    } else {
+                        is_redacted |= Self::redact_json(value);
+                    }
+                }
+                is_redacted
+            }
+            serde_json::Value::Array(array) => {
+                array.iter_mut().fold(false, |is_redacted, value| {
+                    Self::redact_json(value) | is_redacted
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn redacted(&self) -> &str {
+        if self.0.is_empty() {
+            ""
+        } else {
+            Self::REDACTED
+        }
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+impl FromStr for SecretString {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(value))
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretString({})", self.redacted())
+    }
+}
+
+impl Display for SecretString {
+    /// Empty if the string is empty, otherwise [`SecretString::REDACTED`].
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.redacted())
+    }
+}
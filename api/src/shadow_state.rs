@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::CommandRequest;
+
+/// The status of infrared remote devices,
+/// inferred from the commands sent to them.
+///
+/// Infrared remote devices have no status in the [SwitchBot API].
+/// When this is set by [`SwitchBot::set_shadow_state()`][crate::SwitchBot::set_shadow_state()],
+/// successful commands to remote devices update their status,
+/// so that [`Device::status_by_key()`][crate::Device::status_by_key()]
+/// and conditions work for them.
+/// * `turnOn` and `turnOff` set `power` to `on` or `off`.
+/// * `toggle` flips `power` if it's known.
+/// * `setAll:26,1,3,on` sets `temperature`, `mode`, `fanSpeed`, and `power`.
+/// * `SetChannel:15` sets `channel`.
+///
+/// The status may differ from the actual device,
+/// for example when the device is operated by its own remote.
+/// Please see [`Device::is_status_inferred()`][crate::Device::is_status_inferred()].
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+///
+/// # Examples
+/// ```no_run
+/// # use switchbot_api::{ShadowState, SwitchBot};
+/// # async fn test(switch_bot: &mut SwitchBot) -> anyhow::Result<()> {
+/// switch_bot.set_shadow_state(ShadowState::load("shadow_state.json")?);
+/// switch_bot.load_devices().await?;
+/// // Send commands...
+/// switch_bot.shadow_state().unwrap().save("shadow_state.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct ShadowState(HashMap<String, HashMap<String, serde_json::Value>>);
+
+impl ShadowState {
+    /// Load from a JSON file. Empty if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Save to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The inferred status of the device.
+    pub fn get(&self, device_id: &str) -> Option<&HashMap<String, serde_json::Value>> {
+        self.0.get(device_id)
+    }
+
+    pub(crate) fn insert(&mut self, device_id: &str, status: HashMap<String, serde_json::Value>) {
+        if status.is_empty() {
+            self.0.remove(device_id);
+        } else {
+            self.0.insert(device_id.into(), status);
+        }
+    }
+
+    /// Update the `status` by the `command`.
+    /// Returns `true` if the `status` is updated.
+    pub(crate) fn apply_command(
+        status: &mut HashMap<String, serde_json::Value>,
+        command: &CommandRequest,
+    ) -> bool {
+        if !command.command_type.is_empty() && command.command_type != "command" {
+            return false;
+        }
+        let updates: Vec<(&str, serde_json::Value)> = match command.command.as_str() {
+            "turnOn" => vec![("power", "on".into())],
+            "turnOff" => vec![("power", "off".into())],
+            "toggle" => match status.get("power").and_then(|power| power.as_str()) {
+                Some("on") => vec![("power", "off".into())],
+                Some("off") => vec![("power", "on".into())],
+                _ => return false,
+            },
+            "setAll" => {
+                let values: Vec<&str> = command.parameter.split(',').map(str::trim).collect();
+                let [temperature, mode, fan_speed, power] = values.as_slice() else {
+                    return false;
+                };
+                let (Some(temperature), Some(mode), Some(fan_speed)) = (
+                    Self::parse_number(temperature),
+                    Self::parse_number(mode),
+                    Self::parse_number(fan_speed),
+                ) else {
+                    return false;
+                };
+                vec![
+                    ("temperature", temperature),
+                    ("mode", mode),
+                    ("fanSpeed", fan_speed),
+                    ("power", (*power).into()),
+                ]
+            }
+            "SetChannel" => match Self::parse_number(&command.parameter) {
+                Some(channel) => vec![("channel", channel)],
+                None => return false,
+            },
+            _ => return false,
+        };
+        for (key, value) in updates {
+            status.insert(key.into(), value);
+        }
+        true
+    }
+
+    fn parse_number(str: &str) -> Option<serde_json::Value> {
+        if let Ok(value) = str.parse::<i64>() {
+            return Some(value.into());
+        }
+        str.parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply(status: &mut HashMap<String, serde_json::Value>, command: &str) -> bool {
+        ShadowState::apply_command(status, &CommandRequest::from(command))
+    }
+
+    #[test]
+    fn apply_command() {
+        let mut status = HashMap::new();
+        assert!(apply(&mut status, "turnOn"));
+        assert_eq!(status.get("power"), Some(&json!("on")));
+        assert!(apply(&mut status, "setAll:26,1,3,off"));
+        assert_eq!(status.get("temperature"), Some(&json!(26)));
+        assert_eq!(status.get("mode"), Some(&json!(1)));
+        assert_eq!(status.get("fanSpeed"), Some(&json!(3)));
+        assert_eq!(status.get("power"), Some(&json!("off")));
+        assert!(apply(&mut status, "SetChannel:15"));
+        assert_eq!(status.get("channel"), Some(&json!(15)));
+        assert!(apply(&mut status, "toggle"));
+        assert_eq!(status.get("power"), Some(&json!("on")));
+        assert!(apply(&mut status, "toggle"));
+        assert_eq!(status.get("power"), Some(&json!("off")));
+        // `toggle` can't infer the unknown power.
+        assert!(!apply(&mut HashMap::new(), "toggle"));
+
+        assert!(!apply(&mut status, "volumeAdd"));
+        assert!(!apply(&mut status, "customize/turnOn"));
+        assert!(!apply(&mut status, "setAll:26,1"));
+        assert!(!apply(&mut status, "setAll:hot,1,3,on"));
+        assert_eq!(status.get("power"), Some(&json!("off")));
+    }
+
+    #[test]
+    fn load_save() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "switchbot-shadow-state-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        assert!(ShadowState::load(&path)?.is_empty());
+        let mut state = ShadowState::default();
+        state.insert("IR1", HashMap::from([("power".into(), json!("on"))]));
+        state.save(&path)?;
+        let loaded = ShadowState::load(&path)?;
+        fs::remove_file(&path)?;
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.get("IR1").unwrap().get("power"), Some(&json!("on")));
+        Ok(())
+    }
+}
@@ -1,17 +1,59 @@
-use std::sync::Arc;
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use futures_util::{StreamExt, stream};
 
 use super::*;
 
 /// Represents a [SwitchBot API] server.
 ///
 /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct SwitchBot {
-    service: Arc<SwitchBotService>,
+    accounts: Vec<Account>,
     devices: DeviceList,
+    transport: Arc<dyn Transport>,
+    cassette: Option<Arc<Cassette>>,
+    audit_log: Option<Arc<AuditLog>>,
+    api_stats: Arc<ApiStats>,
+    concurrency_limit: usize,
+    shadow_state: Option<ShadowState>,
+}
+
+impl Default for SwitchBot {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            devices: DeviceList::default(),
+            transport: Arc::new(ReqwestTransport::new()),
+            cassette: None,
+            audit_log: None,
+            api_stats: Arc::default(),
+            concurrency_limit: Self::DEFAULT_CONCURRENCY_LIMIT,
+            shadow_state: None,
+        }
+    }
+}
+
+/// A SwitchBot account and its [`SwitchBotService`].
+#[derive(Debug)]
+struct Account {
+    name: String,
+    service: Arc<SwitchBotService>,
 }
 
 impl SwitchBot {
+    /// The default of [`set_concurrency_limit()`][SwitchBot::set_concurrency_limit()].
+    pub const DEFAULT_CONCURRENCY_LIMIT: usize = 4;
+
+    /// The log target of the HTTP requests and responses,
+    /// with the authentication headers redacted.
+    /// They are logged at the [`Trace`][log::Level::Trace] level.
+    /// This is for debugging, such as the `--dump-http` option of the CLI.
+    pub const HTTP_LOG_TARGET: &str = "switchbot_api::http";
+
+    /// The hub ID of devices that are not connected to hubs.
+    const NO_HUB_ID: &str = "000000000000";
+
     /// Construct a new instance with the default parameters.
     pub fn new() -> Self {
         Self::default()
@@ -33,10 +75,32 @@ impl SwitchBot {
     /// # }
     /// ```
     pub fn new_with_authentication(token: &str, secret: &str) -> Self {
-        Self {
-            service: SwitchBotService::new(token, secret),
-            ..Default::default()
+        let mut switch_bot = Self::new();
+        switch_bot.set_authentication(token, secret);
+        switch_bot
+    }
+
+    /// Construct a new instance with the [`Credentials`]
+    /// from the [`CredentialProvider`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use switchbot_api::{Credentials, SwitchBot};
+    /// # fn test() -> anyhow::Result<()> {
+    /// let credentials = Credentials::new("token", "secret");
+    /// let switch_bot = SwitchBot::new_with_credential_provider(&credentials)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_credential_provider(provider: &dyn CredentialProvider) -> anyhow::Result<Self> {
+        let credentials = provider.credentials()?;
+        if credentials.is_empty() {
+            anyhow::bail!("The token and the secret are required");
         }
+        Ok(Self::new_with_authentication(
+            credentials.token(),
+            credentials.secret(),
+        ))
     }
 
     /// Construct an instance for testing.
@@ -52,17 +116,202 @@ impl SwitchBot {
         }
     }
 
+    /// Construct an instance for testing, with the [`Transport`]
+    /// that scripts the responses.
+    /// No authentication information is needed.
+    ///
+    /// Please see [`Transport`] for an example.
+    pub fn new_for_test_with_transport(transport: impl Transport + 'static) -> Self {
+        let mut switch_bot = Self::new();
+        switch_bot.set_transport(transport);
+        switch_bot.set_authentication("", "");
+        switch_bot
+    }
+
+    /// Construct an instance for testing, with devices in multiple accounts.
+    /// `accounts` is a list of the account names and the number of devices.
+    pub fn new_for_test_with_accounts(accounts: &[(&str, usize)]) -> Self {
+        let mut devices = DeviceList::new();
+        for (name, num_devices) in accounts {
+            for _ in 0..*num_devices {
+                let mut device = Device::new_for_test(devices.len() + 1);
+                device.set_account(name);
+                devices.push(device);
+            }
+        }
+        Self {
+            devices,
+            ..Default::default()
+        }
+    }
+
     /// Set the authentication information.
     ///
     /// Please refer to the [SwitchBot documentation about
     /// how to obtain the token and secret key][token-secret].
     ///
     /// [token-secret]: https://github.com/OpenWonderLabs/SwitchBotAPI#getting-started
+    ///
+    /// This removes all accounts added by [`add_account()`][SwitchBot::add_account()].
     pub fn set_authentication(&mut self, token: &str, secret: &str) {
-        self.service = SwitchBotService::new(token, secret);
+        self.accounts = vec![self.create_account("", token, secret)];
         self.devices.clear();
     }
 
+    /// Add a named account with its authentication information.
+    /// If an account with the same `name` exists, it is replaced.
+    ///
+    /// The devices of all accounts are merged into one [`devices()`][SwitchBot::devices()],
+    /// and [`Device::account()`] tells which account each device belongs to.
+    /// The device list is cleared,
+    /// call [`load_devices()`][SwitchBot::load_devices()] to populate it again.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::SwitchBot;
+    /// # async fn test() -> anyhow::Result<()> {
+    /// let mut switch_bot = SwitchBot::new();
+    /// switch_bot.add_account("home", "home_token", "home_secret");
+    /// switch_bot.add_account("office", "office_token", "office_secret");
+    /// switch_bot.load_devices().await?;
+    /// for device in switch_bot.devices().iter() {
+    ///     println!("{}: {}", device.account(), device);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_account(&mut self, name: &str, token: &str, secret: &str) {
+        let account = self.create_account(name, token, secret);
+        if let Some(existing) = self.accounts.iter_mut().find(|a| a.name == name) {
+            *existing = account;
+        } else {
+            self.accounts.push(account);
+        }
+        self.devices.clear();
+    }
+
+    fn create_account(&self, name: &str, token: &str, secret: &str) -> Account {
+        let service = SwitchBotService::new(token, secret, self.transport.clone());
+        service.set_cassette(self.cassette.clone());
+        service.set_audit_log(self.audit_log.clone());
+        service.set_api_stats(self.api_stats.clone());
+        Account {
+            name: name.into(),
+            service,
+        }
+    }
+
+    /// The names of the accounts.
+    /// The account set by [`set_authentication()`][SwitchBot::set_authentication()]
+    /// has an empty name.
+    pub fn account_names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|account| account.name.as_str())
+    }
+
+    /// Set the [`Transport`] to send HTTP requests.
+    /// The default is [`ReqwestTransport`].
+    ///
+    /// This applies to all accounts.
+    /// The device list is cleared,
+    /// call [`load_devices()`][SwitchBot::load_devices()] to populate it again.
+    pub fn set_transport(&mut self, transport: impl Transport + 'static) {
+        self.transport = Arc::new(transport);
+        for account in &mut self.accounts {
+            account.service = account.service.with_transport(self.transport.clone());
+        }
+        self.devices.clear();
+    }
+
+    /// Record the API traffic to, or replay it from, the [`Cassette`].
+    ///
+    /// When replaying, no authentication information is needed,
+    /// and the network is not accessed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use switchbot_api::{Cassette, SwitchBot};
+    /// # async fn test(token: &str, secret: &str) -> anyhow::Result<()> {
+    /// let mut switch_bot = SwitchBot::new_with_authentication(token, secret);
+    /// switch_bot.set_cassette(Cassette::record("cassette.json"));
+    /// switch_bot.load_devices().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_cassette(&mut self, cassette: Cassette) {
+        let cassette = Arc::new(cassette);
+        if cassette.is_replay() && self.accounts.is_empty() {
+            self.accounts.push(self.create_account("", "", ""));
+        }
+        for account in &self.accounts {
+            account.service.set_cassette(Some(cassette.clone()));
+        }
+        self.cassette = Some(cassette);
+    }
+
+    /// Record the commands sent to devices of all accounts to the [`AuditLog`].
+    ///
+    /// The [`AuditLog::set_source()`] can change the [`CommandSource`]
+    /// of the following commands by [`audit_log()`][SwitchBot::audit_log()].
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        let audit_log = Arc::new(audit_log);
+        for account in &self.accounts {
+            account.service.set_audit_log(Some(audit_log.clone()));
+        }
+        self.audit_log = Some(audit_log);
+    }
+
+    /// The [`AuditLog`] set by [`set_audit_log()`][SwitchBot::set_audit_log()].
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_deref()
+    }
+
+    /// The statistics of the calls to the SwitchBot API of all accounts.
+    pub fn api_stats(&self) -> &ApiStats {
+        &self.api_stats
+    }
+
+    /// Set the maximum number of hubs that
+    /// [`command_many()`][SwitchBot::command_many()] and
+    /// [`update_status_many()`][SwitchBot::update_status_many()]
+    /// send requests to concurrently.
+    /// The default is [`SwitchBot::DEFAULT_CONCURRENCY_LIMIT`].
+    /// `1` makes them sequential.
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.concurrency_limit = limit.max(1);
+    }
+
+    /// Infer the status of infrared remote devices from the commands sent to them,
+    /// starting from the `state`.
+    /// Please see [`ShadowState`].
+    pub fn set_shadow_state(&mut self, state: ShadowState) {
+        self.shadow_state = Some(state);
+        self.apply_shadow_state();
+    }
+
+    /// The current [`ShadowState`],
+    /// or `None` if [`set_shadow_state()`][SwitchBot::set_shadow_state()] is not called.
+    pub fn shadow_state(&self) -> Option<ShadowState> {
+        let mut state = self.shadow_state.clone()?;
+        for device in self.devices.iter() {
+            if let Some(status) = device.inferred_status() {
+                state.insert(device.device_id(), status);
+            }
+        }
+        Some(state)
+    }
+
+    fn apply_shadow_state(&mut self) {
+        let Some(state) = &self.shadow_state else {
+            return;
+        };
+        for device in self.devices.iter_mut() {
+            if device.is_remote() && !device.is_status_inferred() {
+                let status = state.get(device.device_id()).cloned().unwrap_or_default();
+                device.set_inferred_status(status);
+            }
+        }
+    }
+
     /// Returns a list of [`Device`]s.
     /// This list is empty initially.
     /// Call [`load_devices()`][SwitchBot::load_devices()] to populate the list.
@@ -71,9 +320,385 @@ impl SwitchBot {
     }
 
     /// Load the device list from the SwitchBot API.
+    ///
+    /// If there are multiple accounts,
+    /// the devices are listed in the order of the accounts.
     pub async fn load_devices(&mut self) -> anyhow::Result<()> {
-        let devices = self.service.load_devices().await?;
+        if self.accounts.is_empty() {
+            anyhow::bail!("No authentication information");
+        }
+        let mut devices = DeviceList::new();
+        for account in &self.accounts {
+            let mut account_devices = account.service.load_devices().await.map_err(|error| {
+                if account.name.is_empty() {
+                    error
+                } else {
+                    error.context(format!(r#"Failed to load devices of "{}""#, account.name))
+                }
+            })?;
+            for device in account_devices.iter_mut() {
+                device.set_account(&account.name);
+            }
+            devices.extend(account_devices);
+        }
         self.devices = devices;
+        self.apply_shadow_state();
         Ok(())
     }
 }
+
+impl SwitchBot {
+    /// Send the `command` to the devices of `device_ids`,
+    /// and returns the per-device results.
+    ///
+    /// Devices connected to the same hub are sent in the order of `device_ids`,
+    /// one by one. Devices of different hubs are sent concurrently,
+    /// up to the [`set_concurrency_limit()`][SwitchBot::set_concurrency_limit()].
+    pub async fn command_many(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+    ) -> BatchReport<CommandResponse> {
+        self.for_each_device(device_ids, |device| device.command(command))
+            .await
+    }
+
+    /// Send the `command` to the devices of `device_ids`
+    /// by [`Device::command_if_needed()`],
+    /// and returns the per-device results.
+    /// The result is `None` for the devices the `command` is skipped for.
+    ///
+    /// Please see [`command_many()`][SwitchBot::command_many()] for the ordering.
+    pub async fn command_many_if_needed(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+    ) -> BatchReport<Option<CommandResponse>> {
+        self.for_each_device(device_ids, |device| device.command_if_needed(command))
+            .await
+    }
+
+    /// Send the `command` to the devices of `device_ids`
+    /// by [`Device::command_and_wait()`]
+    /// with their [`Device::expected_status()`],
+    /// and returns the per-device results.
+    /// The result is an error without sending the `command`
+    /// if the expected status is unknown.
+    ///
+    /// Please see [`command_many()`][SwitchBot::command_many()] for the ordering.
+    pub async fn command_many_and_wait(
+        &self,
+        device_ids: &[&str],
+        command: &CommandRequest,
+        timeout: Duration,
+    ) -> BatchReport<CommandResponse> {
+        self.for_each_device(device_ids, |device| async move {
+            let Some(expected) = device.expected_status(command) else {
+                anyhow::bail!("The status after {command} is unknown for {device}");
+            };
+            device.command_and_wait(command, &expected, timeout).await
+        })
+        .await
+    }
+
+    /// Update the status of the devices of `device_ids`,
+    /// and returns the per-device results.
+    ///
+    /// Please see [`command_many()`][SwitchBot::command_many()] for the ordering.
+    pub async fn update_status_many(&self, device_ids: &[&str]) -> BatchReport {
+        self.for_each_device(device_ids, Device::update_status)
+            .await
+    }
+
+    async fn for_each_device<'a, T, F, Fut>(&'a self, device_ids: &[&str], f: F) -> BatchReport<T>
+    where
+        F: Fn(&'a Device) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut results: Vec<Option<anyhow::Result<T>>> = device_ids.iter().map(|_| None).collect();
+
+        // Group the devices by their hubs, in the order of the first appearance.
+        let mut groups: Vec<Vec<(usize, &Device)>> = Vec::new();
+        let mut group_index_by_hub: HashMap<&str, usize> = HashMap::new();
+        for (i, device_id) in device_ids.iter().enumerate() {
+            let Some(device) = self
+                .devices
+                .index_by_device_id(device_id)
+                .map(|index| &self.devices[index])
+            else {
+                results[i] = Some(Err(anyhow::anyhow!(r#"Device "{device_id}" not found"#)));
+                continue;
+            };
+            let hub = match device.hub_device_id() {
+                "" | Self::NO_HUB_ID => device.device_id(),
+                hub => hub,
+            };
+            let group_index = *group_index_by_hub.entry(hub).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group_index].push((i, device));
+        }
+
+        let f = &f;
+        let group_results: Vec<Vec<(usize, anyhow::Result<T>)>> = stream::iter(groups)
+            .map(|group| async move {
+                let mut group_results = Vec::with_capacity(group.len());
+                for (i, device) in group {
+                    group_results.push((i, f(device).await));
+                }
+                group_results
+            })
+            .buffer_unordered(self.concurrency_limit)
+            .collect()
+            .await;
+        for (i, result) in group_results.into_iter().flatten() {
+            results[i] = Some(result);
+        }
+
+        BatchReport::new(
+            device_ids
+                .iter()
+                .zip(results)
+                .map(|(device_id, result)| (device_id.to_string(), result.unwrap()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn transport() -> anyhow::Result<()> {
+        let transport = ScriptedTransport::default()
+            .device_list(&[
+                json!({"deviceId": "D1", "deviceName": "Bot", "deviceType": "Bot", "hubDeviceId": ""}),
+            ])
+            .ok(json!({}));
+        let requests = transport.requests();
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices().await?;
+        assert_eq!(switch_bot.devices().len(), 1);
+
+        let command = CommandRequest {
+            command: "turnOn".into(),
+            ..Default::default()
+        };
+        switch_bot.devices()[0].command(&command).await?;
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "POST");
+        assert!(requests[1].url.ends_with("/devices/D1/commands"));
+        assert_eq!(requests[1].body.as_ref().unwrap()["command"], "turnOn");
+        assert_eq!(switch_bot.api_stats().total_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn add_account() {
+        let mut switch_bot = SwitchBot::new_with_authentication("token", "secret");
+        switch_bot.add_account("home", "home_token", "home_secret");
+        switch_bot.add_account("office", "office_token", "office_secret");
+        assert_eq!(
+            switch_bot.account_names().collect::<Vec<_>>(),
+            ["", "home", "office"]
+        );
+
+        // The same name replaces the existing account.
+        switch_bot.add_account("home", "new_token", "new_secret");
+        assert_eq!(switch_bot.account_names().count(), 3);
+        assert_eq!(switch_bot.accounts[1].service.token(), "new_token");
+
+        switch_bot.set_authentication("token", "secret");
+        assert_eq!(switch_bot.account_names().collect::<Vec<_>>(), [""]);
+    }
+
+    #[tokio::test]
+    async fn command_many() -> anyhow::Result<()> {
+        let transport = ScriptedTransport::default()
+            .device_list(&[
+                json!({"deviceId": "D1", "deviceName": "1", "deviceType": "Bot", "hubDeviceId": "H"}),
+                json!({"deviceId": "D2", "deviceName": "2", "deviceType": "Bot", "hubDeviceId": "H"}),
+                json!({"deviceId": "D3", "deviceName": "3", "deviceType": "Bot", "hubDeviceId": ""}),
+            ])
+            .ok(json!({}))
+            .ok(json!({}))
+            .ok(json!({}));
+        let requests = transport.requests();
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices().await?;
+
+        let report = switch_bot
+            .command_many(&["D2", "X", "D1", "D3"], &CommandRequest::from("turnOn"))
+            .await;
+        let ids: Vec<&str> = report.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["D2", "X", "D1", "D3"]);
+        assert_eq!(report.num_failed(), 1);
+        assert_eq!(report.failures().next().unwrap().0, "X");
+
+        // Devices of the same hub are in the given order.
+        let requests = requests.lock().unwrap();
+        let position = |id: &str| {
+            requests
+                .iter()
+                .position(|r| r.url.contains(&format!("/{id}/")))
+                .unwrap()
+        };
+        assert!(position("D2") < position("D1"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay() -> anyhow::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("switchbot-cassette-{}.json", uuid::Uuid::new_v4()));
+        let transport = ScriptedTransport::default().device_list(&[
+            json!({"deviceId": "D1", "deviceName": "Bot", "deviceType": "Bot", "hubDeviceId": ""}),
+            json!({"deviceId": "R1", "deviceName": "TV", "remoteType": "TV", "hubDeviceId": "D1"}),
+        ]);
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.set_cassette(Cassette::record(&path));
+        switch_bot.load_devices().await?;
+
+        // Replaying doesn't send requests.
+        let transport = ScriptedTransport::default();
+        let requests = transport.requests();
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.set_cassette(Cassette::replay(&path)?);
+        std::fs::remove_file(&path)?;
+        switch_bot.load_devices().await?;
+        let ids: Vec<&str> = switch_bot.devices().iter().map(Device::device_id).collect();
+        assert_eq!(ids, ["D1", "R1"]);
+        assert!(requests.lock().unwrap().is_empty());
+
+        // The recorded interaction is used up.
+        assert!(switch_bot.load_devices().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shadow_state() -> anyhow::Result<()> {
+        let transport = ScriptedTransport::default()
+            .device_list(&[
+                json!({"deviceId": "D1", "deviceName": "Hub", "deviceType": "Hub 2", "hubDeviceId": ""}),
+                json!({"deviceId": "R1", "deviceName": "TV", "remoteType": "TV", "hubDeviceId": "D1"}),
+                json!({"deviceId": "R2", "deviceName": "AC", "remoteType": "Air Conditioner", "hubDeviceId": "D1"}),
+            ])
+            .ok(json!({}))
+            .ok(json!({}));
+        let requests = transport.requests();
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        let mut state = ShadowState::default();
+        state.insert("R1", HashMap::from([("power".into(), json!("on"))]));
+        switch_bot.set_shadow_state(state);
+        switch_bot.load_devices().await?;
+        let devices = switch_bot.devices();
+        assert!(!devices[0].is_status_inferred());
+        assert!(devices[1].is_status_inferred());
+        assert!(devices[1].eval_condition("power=on")?);
+
+        // The inferred status doesn't send requests.
+        devices[1].update_status().await?;
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        devices[1].command(&CommandRequest::from("turnOff")).await?;
+        devices[2]
+            .command(&CommandRequest::from("setAll:26,1,3,on"))
+            .await?;
+        assert!(devices[1].eval_condition("power=off")?);
+        assert!(devices[2].eval_condition("temperature>25")?);
+
+        let state = switch_bot.shadow_state().unwrap();
+        assert_eq!(state.get("R1").unwrap()["power"], "off");
+        assert_eq!(state.get("R2").unwrap()["fanSpeed"], 3);
+        assert!(state.get("D1").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn command_if_needed() -> anyhow::Result<()> {
+        let status = |power: &str| json!({"deviceId": "D1", "hubDeviceId": "", "power": power});
+        let transport = ScriptedTransport::default()
+            .device_list(&[json!(
+                {"deviceId": "D1", "deviceName": "Plug", "deviceType": "Plug Mini (JP)", "hubDeviceId": ""}
+            )])
+            .ok(status("on"))
+            .ok(json!({}))
+            .ok(json!({}))
+            .ok(status("off"));
+        let requests = transport.requests();
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices().await?;
+        let device = &switch_bot.devices()[0];
+
+        let turn_on = CommandRequest::from("turnOn");
+        let turn_off = CommandRequest::from("turnOff");
+        assert!(!device.has_status());
+        assert!(device.command_if_needed(&turn_on).await?.is_none());
+        // The cached status doesn't send requests.
+        assert!(device.has_status());
+        assert!(device.command_if_needed(&turn_on).await?.is_none());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert!(device.command_if_needed(&turn_off).await?.is_some());
+        // Commands without the expected status are sent without the status.
+        assert!(
+            device
+                .command_if_needed(&CommandRequest::from("toggle"))
+                .await?
+                .is_some()
+        );
+        // Commands clear the cached status.
+        assert!(!device.has_status());
+        assert!(device.command_if_needed(&turn_off).await?.is_none());
+        let requests = requests.lock().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, ["GET", "GET", "POST", "POST", "GET"]);
+        assert_eq!(requests[2].body.as_ref().unwrap()["command"], "turnOff");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn command_and_wait() -> anyhow::Result<()> {
+        let status = |lock_state: &str| json!({"deviceId": "D1", "hubDeviceId": "", "lockState": lock_state});
+        let transport = ScriptedTransport::default()
+            .device_list(&[json!(
+                {"deviceId": "D1", "deviceName": "Lock", "deviceType": "Smart Lock", "hubDeviceId": ""}
+            )])
+            .ok(json!({}))
+            .ok(status("locked"))
+            .ok(json!({}))
+            .ok(status("locked"));
+        let mut switch_bot = SwitchBot::new_for_test_with_transport(transport);
+        switch_bot.load_devices().await?;
+        let device = &switch_bot.devices()[0];
+
+        let lock = CommandRequest::from("lock");
+        let expected = device.expected_status(&lock).unwrap();
+        device
+            .command_and_wait(&lock, &expected, Duration::from_secs(10))
+            .await?;
+
+        let unlock = CommandRequest::from("unlock");
+        let expected = device.expected_status(&unlock).unwrap();
+        let error = device
+            .command_and_wait(&unlock, &expected, Duration::ZERO)
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("the last lockState is \"locked\"")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn new_for_test_with_accounts() {
+        let switch_bot = SwitchBot::new_for_test_with_accounts(&[("home", 2), ("office", 1)]);
+        let accounts: Vec<&str> = switch_bot.devices().iter().map(Device::account).collect();
+        assert_eq!(accounts, ["home", "home", "office"]);
+    }
+}
@@ -2,34 +2,79 @@ use base64::{Engine as _, engine::general_purpose::STANDARD};
 use hmac::{Hmac, KeyInit, Mac};
 use sha2::Sha256;
 use std::{
-    sync::Arc,
+    fmt::Write as _,
+    sync::{Arc, RwLock},
     time::{Instant, SystemTime},
 };
 use uuid::Uuid;
 
 use super::*;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct SwitchBotService {
-    client: reqwest::Client,
-    token: String,
-    secret: String,
+    transport: Arc<dyn Transport>,
+    token: SecretString,
+    secret: SecretString,
+    cassette: RwLock<Option<Arc<Cassette>>>,
+    audit_log: RwLock<Option<Arc<AuditLog>>>,
+    api_stats: RwLock<Arc<ApiStats>>,
 }
 
 impl SwitchBotService {
     const HOST: &str = "https://api.switch-bot.com";
 
-    pub fn new(token: &str, secret: &str) -> Arc<Self> {
+    pub fn new(token: &str, secret: &str, transport: Arc<dyn Transport>) -> Arc<Self> {
         Arc::new(SwitchBotService {
-            client: reqwest::Client::new(),
-            token: token.to_string(),
-            secret: secret.to_string(),
+            transport,
+            token: token.into(),
+            secret: secret.into(),
+            cassette: RwLock::default(),
+            audit_log: RwLock::default(),
+            api_stats: RwLock::default(),
         })
     }
 
+    /// Create a new instance with the same authentication and the `transport`.
+    pub fn with_transport(&self, transport: Arc<dyn Transport>) -> Arc<Self> {
+        let service = Self::new(self.token.expose(), self.secret.expose(), transport);
+        service.set_cassette(self.cassette());
+        service.set_audit_log(self.audit_log());
+        service.set_api_stats(self.api_stats());
+        service
+    }
+
+    #[cfg(test)]
+    pub fn token(&self) -> &str {
+        self.token.expose()
+    }
+
+    pub fn set_cassette(&self, cassette: Option<Arc<Cassette>>) {
+        *self.cassette.write().unwrap() = cassette;
+    }
+
+    fn cassette(&self) -> Option<Arc<Cassette>> {
+        self.cassette.read().unwrap().clone()
+    }
+
+    pub fn set_audit_log(&self, audit_log: Option<Arc<AuditLog>>) {
+        *self.audit_log.write().unwrap() = audit_log;
+    }
+
+    pub(crate) fn audit_log(&self) -> Option<Arc<AuditLog>> {
+        self.audit_log.read().unwrap().clone()
+    }
+
+    pub fn set_api_stats(&self, api_stats: Arc<ApiStats>) {
+        *self.api_stats.write().unwrap() = api_stats;
+    }
+
+    fn api_stats(&self) -> Arc<ApiStats> {
+        self.api_stats.read().unwrap().clone()
+    }
+
     pub async fn load_devices(self: &Arc<SwitchBotService>) -> anyhow::Result<DeviceList> {
         let url = format!("{}/v1.1/devices", Self::HOST);
-        let request = self.client.get(url);
+        let request = TransportRequest::get(url);
         let device_list = self.send_as::<DeviceListResponse>(request).await?;
 
         let mut devices = DeviceList::with_capacity(
@@ -47,18 +92,23 @@ impl SwitchBotService {
         &self,
         device_id: &str,
         command: &CommandRequest,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<CommandResponse> {
         let url = format!("{}/v1.1/devices/{device_id}/commands", Self::HOST);
         let body = serde_json::to_value(command)?;
-        log::debug!("command.request: {body}");
-        let request = self.client.post(url).json(&body);
-        self.send_as_opt(request).await?;
-        Ok(())
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!(
+                "command.request: {}",
+                serde_json::to_value(command.redacted())?
+            );
+        }
+        let request = TransportRequest::post(url, body);
+        let body = self.send_as_opt(request).await?;
+        Ok(CommandResponse::new(body))
     }
 
     pub(crate) async fn status(&self, device_id: &str) -> anyhow::Result<Option<Device>> {
         let url = format!("{}/v1.1/devices/{device_id}/status", Self::HOST);
-        let request = self.client.get(url);
+        let request = TransportRequest::get(url);
         let body_json = self.send_as_json(request).await?;
         if let serde_json::Value::Object(object) = &body_json {
             // Hub Mini returns `"body":{}`. Make this not an error.
@@ -72,17 +122,14 @@ impl SwitchBotService {
 
     async fn send_as<T: serde::de::DeserializeOwned>(
         &self,
-        request: reqwest::RequestBuilder,
+        request: TransportRequest,
     ) -> anyhow::Result<T> {
         let body_json = self.send_as_json(request).await?;
         let body: T = serde_json::from_value(body_json)?;
         Ok(body)
     }
 
-    async fn send_as_json(
-        &self,
-        request: reqwest::RequestBuilder,
-    ) -> anyhow::Result<serde_json::Value> {
+    async fn send_as_json(&self, request: TransportRequest) -> anyhow::Result<serde_json::Value> {
         let body_json = self
             .send_as_opt(request)
             .await?
@@ -92,16 +139,110 @@ impl SwitchBotService {
 
     async fn send_as_opt(
         &self,
-        request: reqwest::RequestBuilder,
+        request: TransportRequest,
     ) -> anyhow::Result<Option<serde_json::Value>> {
+        let cassette = self.cassette();
+        if let Some(cassette) = &cassette
+            && cassette.is_replay()
+        {
+            return Self::replay(cassette, &request);
+        }
+
         let start_time = Instant::now();
-        let response = self.add_headers(request)?.send().await?;
-        log::trace!("response: {response:?}");
-        response.error_for_status_ref()?;
+        let request = self.add_headers(request)?;
+        Self::log_request(&request);
+        let interaction = cassette
+            .as_ref()
+            .map(|_| Interaction::new(&request, 0, serde_json::Value::Null));
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let result = self.transport.send(request).await;
+        let elapsed = start_time.elapsed();
+        if let Ok(response) = &result {
+            log::trace!("response: {}: elapsed {elapsed:?}", response.status);
+            log::trace!(target: SwitchBot::HTTP_LOG_TARGET, "< {}", response.body);
+        }
+        if let (Some(cassette), Some(mut interaction)) = (&cassette, interaction) {
+            (interaction.status, interaction.response) = match &result {
+                Ok(response) => (response.status, response.body.clone()),
+                // Errors without responses, such as network errors,
+                // are recorded as the status 0 with the error message.
+                Err(error) => (0, format!("{error:#}").into()),
+            };
+            // The request has already reached the API; don't fail the result.
+            if let Err(error) = cassette.push(interaction) {
+                log::warn!("Failed to record the cassette: {error:#}");
+            }
+        }
+        let result = result
+            .and_then(|response| response.error_for_status(&method, &url))
+            .and_then(Self::body_from_json);
+        self.api_stats().record(&url, elapsed, result.is_ok());
+        result
+    }
+
+    /// Serve the response from the [`Cassette`] instead of the [`Transport`].
+    fn replay(
+        cassette: &Cassette,
+        request: &TransportRequest,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        let interaction = cassette.find(&request.method, &request.url, request.body.as_ref())?;
+        log::trace!(
+            "replay: {} {} {}",
+            interaction.status,
+            request.method,
+            request.url
+        );
+        Self::check_status(
+            interaction.status,
+            &interaction.response,
+            &request.method,
+            &request.url,
+        )?;
+        Self::body_from_json(interaction.response)
+    }
 
-        let json: serde_json::Value = response.json().await?;
-        log::trace!("response.json: {json}: elapsed {:?}", start_time.elapsed());
-        Self::body_from_json(json)
+    /// Returns an error if the HTTP `status` is not successful.
+    /// The status `0` is an error without responses,
+    /// and the `json` is its message.
+    fn check_status(
+        status: u16,
+        json: &serde_json::Value,
+        method: &str,
+        url: &str,
+    ) -> anyhow::Result<()> {
+        if status == 0 {
+            let message = json.as_str().unwrap_or("Failed to send");
+            anyhow::bail!("{message} for {method} {url}");
+        }
+        if !(200..300).contains(&status) {
+            anyhow::bail!("HTTP status {status} for {method} {url}: {json}");
+        }
+        Ok(())
+    }
+
+    fn log_request(request: &TransportRequest) {
+        if log::log_enabled!(target: SwitchBot::HTTP_LOG_TARGET, log::Level::Trace) {
+            log::trace!(
+                target: SwitchBot::HTTP_LOG_TARGET,
+                "{}",
+                Self::format_request(request).trim_end()
+            );
+        }
+    }
+
+    /// Format the request in the HTTP wire-like format,
+    /// with the authentication headers redacted.
+    fn format_request(request: &TransportRequest) -> String {
+        let mut text = format!("> {} {}\n", request.method, request.url);
+        for (name, value) in request.redacted_headers() {
+            let _ = writeln!(text, "> {name}: {value}");
+        }
+        text.push_str(">\n");
+        if let Some(body) = request.redacted_body() {
+            let _ = writeln!(text, "> {body}");
+        }
+        text
     }
 
     fn body_from_json(json: serde_json::Value) -> anyhow::Result<Option<serde_json::Value>> {
@@ -119,23 +260,20 @@ impl SwitchBotService {
         Ok(response.body)
     }
 
-    fn add_headers(
-        &self,
-        builder: reqwest::RequestBuilder,
-    ) -> anyhow::Result<reqwest::RequestBuilder> {
+    fn add_headers(&self, request: TransportRequest) -> anyhow::Result<TransportRequest> {
         let duration_since_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
         let t = duration_since_epoch.as_millis().to_string();
         let nonce = Uuid::new_v4().to_string();
 
-        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())?;
-        mac.update(self.token.as_bytes());
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.expose().as_bytes())?;
+        mac.update(self.token.expose().as_bytes());
         mac.update(t.as_bytes());
         mac.update(nonce.as_bytes());
         let result = mac.finalize();
         let sign = STANDARD.encode(result.into_bytes());
 
-        Ok(builder
-            .header("Authorization", self.token.clone())
+        Ok(request
+            .header("Authorization", self.token.expose())
             .header("t", t)
             .header("sign", sign)
             .header("nonce", nonce))
@@ -182,6 +320,163 @@ impl<T> From<SwitchBotResponse<T>> for SwitchBotError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::io;
+    use std::sync::Once;
+
+    struct CaptureLogger;
+
+    thread_local! {
+        // Each test captures only the logs from its own thread, so tests
+        // running in parallel don't see each other's logs.
+        static CAPTURED_LOGS: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.with_borrow_mut(|logs| {
+                if let Some(logs) = logs {
+                    let _ = writeln!(logs, "{}", record.args());
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture_logs(f: impl FnOnce()) -> String {
+        start_capture_logs();
+        f();
+        take_captured_logs()
+    }
+
+    async fn capture_logs_async<T>(future: impl Future<Output = T>) -> (T, String) {
+        start_capture_logs();
+        let output = future.await;
+        (output, take_captured_logs())
+    }
+
+    fn start_capture_logs() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CaptureLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        CAPTURED_LOGS.set(Some(String::new()));
+    }
+
+    fn take_captured_logs() -> String {
+        CAPTURED_LOGS.take().unwrap_or_default()
+    }
+
+    #[test]
+    fn no_secrets_in_logs() -> anyhow::Result<()> {
+        let service =
+            SwitchBotService::new("my_token", "my_secret", Arc::new(ReqwestTransport::new()));
+        let request = service.add_headers(TransportRequest::post(
+            "https://api.switch-bot.com/v1.1/devices/id/commands",
+            serde_json::json!({"command": "turnOn"}),
+        ))?;
+        let logs = capture_logs(|| {
+            SwitchBotService::log_request(&request);
+            log::trace!("service: {service:?}");
+        });
+        assert!(logs.contains("Authorization: [REDACTED]"), "{logs}");
+        assert!(logs.contains("sign: [REDACTED]"), "{logs}");
+        assert!(logs.contains(r#"{"command":"turnOn"}"#), "{logs}");
+        assert!(!logs.contains("my_token"), "{logs}");
+        assert!(!logs.contains("my_secret"), "{logs}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_replay_errors() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("switchbot-cassette-{}.json", Uuid::new_v4()));
+        let cassette = Arc::new(Cassette::record(&path));
+        let responses = [
+            Ok(
+                TransportResponse::new(401, serde_json::json!({"message": "Unauthorized"}))
+                    .with_error(io::Error::from(io::ErrorKind::PermissionDenied)),
+            ),
+            Err(io::Error::from(io::ErrorKind::ConnectionRefused).into()),
+        ];
+        for response in responses {
+            let transport = ScriptedTransport::default().response(response);
+            let service = SwitchBotService::new("token", "secret", Arc::new(transport));
+            service.set_cassette(Some(cassette.clone()));
+            // The typed errors from the transport are returned as they are.
+            let error = service.status("D1").await.unwrap_err();
+            assert!(error.downcast_ref::<io::Error>().is_some(), "{error}");
+        }
+
+        let replay = Cassette::replay(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(replay.len(), 2);
+        let service =
+            SwitchBotService::new("token", "secret", Arc::new(ScriptedTransport::default()));
+        service.set_cassette(Some(Arc::new(replay)));
+        let error = service.status("D1").await.unwrap_err().to_string();
+        assert!(error.starts_with("HTTP status 401 for GET "), "{error}");
+        assert!(error.contains("Unauthorized"), "{error}");
+        let error = service.status("D1").await.unwrap_err().to_string();
+        assert!(error.starts_with("connection refused for GET "), "{error}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_failure() -> anyhow::Result<()> {
+        let transport = ScriptedTransport::default().ok(serde_json::json!({}));
+        let service = SwitchBotService::new("token", "secret", Arc::new(transport));
+        let path = std::env::temp_dir()
+            .join(format!("switchbot-no-dir-{}", Uuid::new_v4()))
+            .join("cassette.json");
+        service.set_cassette(Some(Arc::new(Cassette::record(&path))));
+        // The command has reached the API even if the cassette failed to write.
+        service
+            .command("D1", &CommandRequest::from("turnOn"))
+            .await?;
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn no_passcode_in_outputs() -> anyhow::Result<()> {
+        let password = "918273";
+        let command = Passcode::permanent("me", password).to_command()?;
+        assert!(!command.to_string().contains(password));
+        assert!(!format!("{command:?}").contains(password));
+
+        let transport = ScriptedTransport::default().ok(serde_json::json!({}));
+        let service = SwitchBotService::new("token", "secret", Arc::new(transport));
+        let path = std::env::temp_dir().join(format!("switchbot-cassette-{}.json", Uuid::new_v4()));
+        service.set_cassette(Some(Arc::new(Cassette::record(&path))));
+        let (result, logs) = capture_logs_async(service.command("D1", &command)).await;
+        result?;
+        assert!(logs.contains("createKey"), "{logs}");
+        assert!(!logs.contains(password), "{logs}");
+
+        let request = TransportRequest::post("url", serde_json::to_value(&command)?);
+        assert_eq!(
+            serde_json::to_value(&command)?["parameter"]["password"],
+            password
+        );
+        assert!(!SwitchBotService::format_request(&request).contains(password));
+        assert!(!format!("{request:?}").contains(password));
+
+        let recorded = std::fs::read_to_string(&path)?;
+        assert!(recorded.contains("createKey"), "{recorded}");
+        assert!(!recorded.contains(password), "{recorded}");
+
+        // The redacted cassette can replay the request.
+        service.set_cassette(Some(Arc::new(Cassette::replay(&path)?)));
+        std::fs::remove_file(&path)?;
+        service.command("D1", &command).await?;
+        Ok(())
+    }
 
     #[test]
     fn body_from_json() {
@@ -0,0 +1,274 @@
+use std::{fmt, fmt::Write as _, future::Future, pin::Pin};
+
+use super::*;
+
+/// The future returned by [`Transport::send()`].
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = anyhow::Result<TransportResponse>> + Send + 'a>>;
+
+/// Sends HTTP requests to the [SwitchBot API].
+///
+/// The default is [`ReqwestTransport`].
+/// Implement this trait to mock the server in tests,
+/// to add middleware, or to use another HTTP stack.
+///
+/// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+///
+/// # Examples
+/// ```
+/// # use switchbot_api::{SwitchBot, Transport, TransportFuture, TransportRequest, TransportResponse};
+/// #[derive(Debug)]
+/// struct NoDevices;
+///
+/// impl Transport for NoDevices {
+///     fn send(&self, _request: TransportRequest) -> TransportFuture<'_> {
+///         Box::pin(async {
+///             Ok(TransportResponse::ok(serde_json::json!({
+///                 "statusCode": 100,
+///                 "message": "success",
+///                 "body": {"deviceList": [], "infraredRemoteList": []},
+///             })))
+///         })
+///     }
+/// }
+///
+/// # async fn test() -> anyhow::Result<()> {
+/// let mut switch_bot = SwitchBot::new_for_test_with_transport(NoDevices);
+/// switch_bot.load_devices().await?;
+/// assert!(switch_bot.devices().is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Send the `request`, and return the HTTP status
+    /// and the whole JSON response,
+    /// including the `statusCode`, the `message`, and the `body`.
+    ///
+    /// HTTP error statuses should be returned as `Ok`, so that they can be recorded.
+    /// Use [`TransportResponse::with_error()`] to return a typed error for them.
+    /// Errors without responses, such as network errors, should be `Err`.
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_>;
+}
+
+/// An HTTP response from [`Transport`].
+#[derive(Debug)]
+pub struct TransportResponse {
+    /// The HTTP status code, such as `200`.
+    pub status: u16,
+    /// The JSON body. A body that is not JSON is a JSON string.
+    pub body: serde_json::Value,
+    error: Option<anyhow::Error>,
+}
+
+impl TransportResponse {
+    /// Construct a response with the HTTP status `200`.
+    pub fn ok(body: serde_json::Value) -> Self {
+        Self::new(200, body)
+    }
+
+    /// Construct a response with the HTTP `status`.
+    pub fn new(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body,
+            error: None,
+        }
+    }
+
+    /// Set the `error` to return if the HTTP status is not successful,
+    /// such as the [`reqwest::Error`] from `error_for_status()`,
+    /// so that callers can downcast it.
+    pub fn with_error(mut self, error: impl Into<anyhow::Error>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// True if the HTTP status is successful (2xx).
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body if the HTTP status is successful,
+    /// otherwise the error set by [`with_error()`][TransportResponse::with_error()],
+    /// or an error with the status and the body.
+    pub(crate) fn error_for_status(
+        self,
+        method: &str,
+        url: &str,
+    ) -> anyhow::Result<serde_json::Value> {
+        if self.is_success() {
+            return Ok(self.body);
+        }
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        anyhow::bail!(
+            "HTTP status {} for {method} {url}: {}",
+            self.status,
+            self.body
+        )
+    }
+}
+
+/// An HTTP request for [`Transport`].
+#[derive(Clone, Default, PartialEq)]
+pub struct TransportRequest {
+    /// The HTTP method, such as `GET` or `POST`.
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// The JSON body, if any.
+    pub body: Option<serde_json::Value>,
+}
+
+impl TransportRequest {
+    /// Headers whose values are replaced with [`SecretString::REDACTED`]
+    /// in logs and dumps.
+    const REDACTED_HEADERS: [&str; 2] = ["authorization", "sign"];
+
+    /// Construct a `GET` request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: "GET".into(),
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Construct a `POST` request with the JSON `body`.
+    pub fn post(url: impl Into<String>, body: serde_json::Value) -> Self {
+        Self {
+            method: "POST".into(),
+            url: url.into(),
+            body: Some(body),
+            ..Default::default()
+        }
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// The headers with the authentication headers redacted.
+    pub fn redacted_headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(name, value)| {
+            if Self::is_redacted_header(name) {
+                (name.as_str(), SecretString::REDACTED)
+            } else {
+                (name.as_str(), value.as_str())
+            }
+        })
+    }
+
+    /// The body with the secrets, such as the `password` of the `createKey` command,
+    /// replaced with [`SecretString::REDACTED`].
+    pub fn redacted_body(&self) -> Option<serde_json::Value> {
+        let mut body = self.body.clone()?;
+        SecretString::redact_json(&mut body);
+        Some(body)
+    }
+
+    pub(crate) fn is_redacted_header(name: &str) -> bool {
+        Self::REDACTED_HEADERS
+            .iter()
+            .any(|redacted| redacted.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Debug for TransportRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &self.redacted_headers().collect::<Vec<_>>())
+            .field("body", &self.redacted_body())
+            .finish()
+    }
+}
+
+/// The default [`Transport`] using [`reqwest`].
+#[derive(Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Construct a new instance with the default [`reqwest::Client`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a new instance with the [`reqwest::Client`],
+    /// such as the one with proxies or timeouts.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    async fn send_core(&self, request: TransportRequest) -> anyhow::Result<TransportResponse> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.json(body);
+        }
+        let response = builder.send().await?;
+        log::trace!("response: {} {}", response.status(), response.url());
+        if log::log_enabled!(target: SwitchBot::HTTP_LOG_TARGET, log::Level::Trace) {
+            log::trace!(
+                target: SwitchBot::HTTP_LOG_TARGET,
+                "{}",
+                Self::format_response_head(&response).trim_end()
+            );
+        }
+        let status = response.status().as_u16();
+        let error = response.error_for_status_ref().err();
+        let text = response.text().await?;
+        let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+        let response = TransportResponse::new(status, body);
+        Ok(match error {
+            Some(error) => response.with_error(error),
+            None => response,
+        })
+    }
+
+    fn format_response_head(response: &reqwest::Response) -> String {
+        let mut text = format!("< {:?} {}\n", response.version(), response.status());
+        for (name, value) in response.headers() {
+            let value = if TransportRequest::is_redacted_header(name.as_str()) {
+                SecretString::REDACTED
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            let _ = writeln!(text, "< {name}: {value}");
+        }
+        text.push_str("<\n");
+        text
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(self.send_core(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_secrets_in_debug() {
+        let request = TransportRequest::get("https://example.com")
+            .header("Authorization", "my_token")
+            .header("sign", "my_sign")
+            .header("t", "123");
+        let debug = format!("{request:?}");
+        assert!(!debug.contains("my_token"), "{debug}");
+        assert!(!debug.contains("my_sign"), "{debug}");
+        assert!(debug.contains("123"), "{debug}");
+    }
+}
@@ -1,9 +1,19 @@
-use std::{collections::HashMap, future::Future, io::stdout, iter::zip};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{IsTerminal, stdin, stdout},
+    iter::zip,
+    time::{Duration, SystemTime},
+};
 
 use itertools::Itertools;
-use switchbot_api::{CommandRequest, Device, DeviceList, Help, SwitchBot};
+use switchbot_api::{
+    AuditLog, AuditRecord, BatchReport, CommandHelp, CommandRequest, CommandScope, CommandSource,
+    Condition, Device, DeviceList, Help, Keypad, KeypadRequest, KeypadRequests, Passcode,
+    PasscodeType, ShadowState, SwitchBot, TimeWindow,
+};
 
-use crate::{Args, UserInput};
+use crate::{Args, CustomButtons, Exporter, MqttBridge, UserInput};
 
 #[derive(Debug, Default)]
 pub struct Cli {
@@ -22,6 +32,12 @@ impl Cli {
         }
     }
 
+    /// True if the `--dump-http` option is specified.
+    /// The logger should print the [`SwitchBot::HTTP_LOG_TARGET`] to stderr.
+    pub fn is_dump_http(&self) -> bool {
+        self.args.dump_http
+    }
+
     #[cfg(test)]
     fn new_for_test(n_devices: usize) -> Self {
         Self {
@@ -66,12 +82,39 @@ impl Cli {
     async fn ensure_devices(&mut self) -> anyhow::Result<()> {
         if self.devices().is_empty() {
             self.switch_bot = self.args.create_switch_bot()?;
+            if self.args.shadow_state == Some(true) {
+                let state = ShadowState::load(Args::shadow_state_path()?)?;
+                self.switch_bot.set_shadow_state(state);
+            }
+            // Replayed commands are not sent to the devices.
+            if self.args.replay.is_none() {
+                // The audit log is best-effort; commands run without it.
+                match Args::audit_log_path().and_then(AuditLog::open) {
+                    Ok(audit_log) => {
+                        audit_log.set_source(self.command_source());
+                        self.switch_bot.set_audit_log(audit_log);
+                    }
+                    Err(error) => log::warn!("Failed to open the audit log: {error:#}"),
+                }
+            }
             self.switch_bot.load_devices().await?;
             log::debug!("ensure_devices: {} devices", self.devices().len());
         }
         Ok(())
     }
 
+    fn command_source(&self) -> CommandSource {
+        if self.args.mqtt.is_some() {
+            CommandSource::Automation
+        } else if !self.args.commands.is_empty() {
+            CommandSource::Batch
+        } else if stdin().is_terminal() {
+            CommandSource::Interactive
+        } else {
+            CommandSource::Script
+        }
+    }
+
     pub async fn run(&mut self) -> anyhow::Result<()> {
         self.args.process()?;
         self.run_core().await?;
@@ -85,10 +128,36 @@ impl Cli {
             self.args.aliases.print();
             is_interactive = false;
         }
+        if self.args.has_account_updates() {
+            self.args.print_accounts();
+            is_interactive = false;
+        }
+        if self.args.encrypt {
+            println!("The credentials are encrypted");
+            is_interactive = false;
+        }
 
-        if !self.args.commands.is_empty() {
+        if let Some(address) = self.args.exporter.clone() {
             self.ensure_devices().await?;
             self.execute_args(&self.args.commands.clone()).await?;
+            self.run_exporter(&address).await?;
+        } else if let Some(url) = self.args.mqtt.clone() {
+            let bridge = MqttBridge::new(&url)?;
+            self.ensure_devices().await?;
+            self.execute_args(&self.args.commands.clone()).await?;
+            let device_ids = self.current_or_all_device_ids(|_| true);
+            let device_ids: Vec<&str> = device_ids.iter().map(String::as_str).collect();
+            bridge.run(&self.switch_bot, &device_ids).await?;
+        } else if !self.args.commands.is_empty() {
+            if !self
+                .args
+                .commands
+                .iter()
+                .all(|text| self.is_offline_command(text))
+            {
+                self.ensure_devices().await?;
+            }
+            self.execute_args(&self.args.commands.clone()).await?;
         } else if is_interactive {
             self.ensure_devices().await?;
             self.run_interactive().await?;
@@ -96,6 +165,48 @@ impl Cli {
         Ok(())
     }
 
+    /// Serve the status of the current devices, or all devices except
+    /// infrared remote devices, as Prometheus metrics.
+    async fn run_exporter(&mut self, address: &str) -> anyhow::Result<()> {
+        let device_ids = self.current_or_all_device_ids(|device| !device.is_remote());
+        let device_ids: Vec<&str> = device_ids.iter().map(String::as_str).collect();
+        let interval = Exporter::refresh_interval(device_ids.len());
+        println!("Refreshing {} devices every {interval:?}", device_ids.len());
+        let exporter = Exporter::default();
+        exporter.listen(&Exporter::parse_address(address)).await?;
+        loop {
+            let report = self.switch_bot.update_status_many(&device_ids).await;
+            for (device_id, error) in report.failures() {
+                log::error!("{device_id}: {error}");
+            }
+            let devices: Vec<(&Device, bool)> = report
+                .iter()
+                .filter_map(|(device_id, result)| {
+                    let index = self.devices().index_by_device_id(device_id)?;
+                    Some((&self.devices()[index], result.is_ok()))
+                })
+                .collect();
+            exporter.set_metrics(Exporter::render(&devices, self.switch_bot.api_stats()));
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// The IDs of the current devices,
+    /// or all devices that match the `filter` if there are no current devices.
+    fn current_or_all_device_ids(&self, filter: impl Fn(&Device) -> bool) -> Vec<String> {
+        if self.has_current_device() {
+            self.current_devices()
+                .map(|device| device.device_id().to_string())
+                .collect()
+        } else {
+            self.devices()
+                .iter()
+                .filter(|device| filter(device))
+                .map(|device| device.device_id().to_string())
+                .collect()
+        }
+    }
+
     async fn run_interactive(&mut self) -> anyhow::Result<()> {
         let mut input = UserInput::new();
         self.print_devices();
@@ -163,8 +274,12 @@ impl Cli {
         index: usize,
         reverse_aliases: &HashMap<&str, Vec<&str>>,
     ) {
+        let account_selector = self.account_selector(index);
         let index = index + 1;
         let mut aliases: Vec<&str> = Vec::new();
+        if let Some(selector) = &account_selector {
+            aliases.push(selector);
+        }
         if let Some(list) = reverse_aliases.get(index.to_string().as_str()) {
             aliases.extend(list);
         }
@@ -179,31 +294,198 @@ impl Cli {
         }
     }
 
+    /// True if the help should be styled by ANSI escape codes;
+    /// i.e., the stdout is a terminal and [`NO_COLOR`] is not set.
+    ///
+    /// [`NO_COLOR`]: https://no-color.org/
+    fn is_ansi() -> bool {
+        stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
+
     const COMMAND_URL: &str = "https://github.com/OpenWonderLabs/SwitchBotAPI#device-specifications-and-supported-features-list";
     const COMMAND_IR_URL: &str = "https://github.com/OpenWonderLabs/SwitchBotAPI/blob/main/devices/others/virtual-infrared-remote-devices.md";
 
-    async fn print_help(&mut self) -> anyhow::Result<()> {
+    async fn ensure_help(&mut self) -> anyhow::Result<&Help> {
         if self.help.is_none() {
-            self.help = Some(Help::load().await?);
+            let mut help = Help::load().await?;
+            for path in [Args::imported_help_data_path(), Args::help_data_path()] {
+                if let Ok(path) = path
+                    && path.exists()
+                {
+                    help.merge(Help::load_from_path(&path)?);
+                }
+            }
+            self.help = Some(help);
         }
+        Ok(self.help.as_ref().unwrap())
+    }
+
+    /// Commands that can run without the authentication nor the device list.
+    const OFFLINE_COMMAND_PREFIXES: [&str; 6] = [
+        "help type:",
+        "help ir:",
+        "help --import ",
+        "help --search ",
+        "keypad list-pending",
+        "history",
+    ];
+
+    fn is_offline_command(&self, text: &str) -> bool {
+        let expanded = self.args.aliases.expand(text);
+        Self::OFFLINE_COMMAND_PREFIXES
+            .iter()
+            .any(|prefix| expanded.starts_with(prefix))
+    }
+
+    fn import_help(&mut self, dir: &str) -> anyhow::Result<()> {
+        let help = Help::from_markdown_docs(dir)?;
+        // Not to overwrite the user's `help_data.json`.
+        let path = Args::imported_help_data_path()?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, help.to_json_string()?)?;
+        println!(
+            "Imported {} device types to {}",
+            help.num_device_types(),
+            path.display()
+        );
+        // Reload with the imported data on the next use.
+        self.help = None;
+        Ok(())
+    }
+
+    async fn print_help(&mut self, show_source: bool) -> anyhow::Result<()> {
+        self.ensure_help().await?;
         let device = self.first_current_device();
         let command_helps = self.help.as_ref().unwrap().command_helps(device);
-        let help_url = if device.is_remote() {
+        let buttons = self.args.custom_buttons.get(device.device_id());
+        if !buttons.is_empty() {
+            println!("[{}]", CustomButtons::COMMAND_TYPE);
+            for button in buttons {
+                println!("{}/{button}", CustomButtons::COMMAND_TYPE);
+            }
+            if command_helps.is_empty() {
+                return Ok(());
+            }
+        }
+        Self::print_command_helps(
+            &command_helps,
+            device.device_type_or_remote_type(),
+            device.is_remote(),
+            show_source,
+        )
+    }
+
+    async fn print_help_for_type(&mut self, name: &str, is_remote: bool) -> anyhow::Result<()> {
+        let help = self.ensure_help().await?;
+        let (types, command_helps) = if is_remote {
+            let name = name.strip_prefix("DIY ").unwrap_or(name);
+            let ir_types = help.ir_types();
+            let command_helps = if ir_types.contains(&name) {
+                help.command_helps_by_remote_type(name)
+            } else {
+                Vec::new()
+            };
+            (ir_types, command_helps)
+        } else {
+            (help.device_types(), help.command_helps_by_device_type(name))
+        };
+        if command_helps.is_empty() {
+            anyhow::bail!(
+                r#"No help for "{name}". Available types are: {}"#,
+                types.join(", ")
+            );
+        }
+        Self::print_command_helps(&command_helps, name, is_remote, false)
+    }
+
+    fn print_command_helps(
+        command_helps: &[&CommandHelp],
+        name: &str,
+        is_remote: bool,
+        show_source: bool,
+    ) -> anyhow::Result<()> {
+        let help_url = if is_remote {
             Self::COMMAND_IR_URL
         } else {
             Self::COMMAND_URL
         };
         if command_helps.is_empty() {
+            anyhow::bail!(r#"No help for "{name}". Please see {help_url} for more information"#)
+        }
+        let mut scope = CommandScope::DeviceType;
+        for command_help in command_helps {
+            if command_help.scope() != scope {
+                scope = command_help.scope();
+                println!("[{scope}]");
+            }
+            println!("{}", command_help.text(Self::is_ansi()));
+            if show_source {
+                println!("    [source: {}]", command_help.source());
+            }
+        }
+        println!("Please see {help_url} for more information");
+        Ok(())
+    }
+
+    async fn print_help_search(&mut self, text: &str) -> anyhow::Result<()> {
+        let help = self.ensure_help().await?;
+        let matches = help.search(text);
+        if matches.is_empty() {
+            anyhow::bail!(r#"No commands found for "{text}""#);
+        }
+        let mut last_device_type = None;
+        for found in matches {
+            let device_type = Some((found.is_remote, found.device_type));
+            if device_type != last_device_type {
+                last_device_type = device_type;
+                println!("[{found}]");
+            }
+            println!("{}", found.command_help.text(Self::is_ansi()));
+        }
+        Ok(())
+    }
+
+    async fn print_devices_supporting(&mut self, command: &str) -> anyhow::Result<()> {
+        self.ensure_help().await?;
+        let help = self.help.as_ref().unwrap();
+        let reverse_aliases = self.args.aliases.reverse_map();
+        let mut is_found = false;
+        for (index, device) in self.devices().iter().enumerate() {
+            if help
+                .command_helps(device)
+                .iter()
+                .any(|command_help| command_help.command().command == command)
+            {
+                self.print_device(device, index, &reverse_aliases);
+                is_found = true;
+            }
+        }
+        if !is_found {
+            anyhow::bail!(r#"No devices support "{command}""#);
+        }
+        Ok(())
+    }
+
+    const STATUS_URL: &str = "https://github.com/OpenWonderLabs/SwitchBotAPI#get-device-status";
+
+    async fn print_status_help(&mut self, show_source: bool) -> anyhow::Result<()> {
+        self.ensure_help().await?;
+        let device = self.first_current_device();
+        let status_helps = self.help.as_ref().unwrap().status_helps(device);
+        if status_helps.is_empty() {
             anyhow::bail!(
-                r#"No help for "{}". Please see {} for more information"#,
+                r#"No status help for "{}". Please see {} for more information"#,
                 device.device_type_or_remote_type(),
-                help_url
+                Self::STATUS_URL
             )
         }
-        for command_help in command_helps {
-            println!("{command_help}");
+        for status_help in status_helps {
+            println!("{}", status_help.text(Self::is_ansi()));
+            if show_source {
+                println!("    [source: {}]", status_help.source());
+            }
         }
-        println!("Please see {help_url} for more information");
+        println!("Please see {} for more information", Self::STATUS_URL);
         Ok(())
     }
 
@@ -224,6 +506,26 @@ impl Cli {
         if self.execute_global_builtin_command(text)? {
             return Ok(());
         }
+        if let Some(dir) = text.strip_prefix("help --import ") {
+            self.import_help(dir.trim())?;
+            return Ok(());
+        }
+        if let Some(name) = text.strip_prefix("help type:") {
+            self.print_help_for_type(name.trim(), false).await?;
+            return Ok(());
+        }
+        if let Some(name) = text.strip_prefix("help ir:") {
+            self.print_help_for_type(name.trim(), true).await?;
+            return Ok(());
+        }
+        if let Some(search) = text.strip_prefix("help --search ") {
+            self.print_help_search(search.trim()).await?;
+            return Ok(());
+        }
+        if let Some(command) = text.strip_prefix("help --who ") {
+            self.print_devices_supporting(command.trim()).await?;
+            return Ok(());
+        }
 
         // If the first word is devices, set current devices and execute the rests.
         let rests_expanded;
@@ -239,8 +541,25 @@ impl Cli {
             if self.execute_if_expr(text).await? {
                 return Ok(());
             }
-            if text == "help" {
-                self.print_help().await?;
+            if text == "help" || text == "help --source" {
+                self.print_help(text.ends_with("--source")).await?;
+                return Ok(());
+            }
+            if text == "help status" || text == "help status --source" {
+                self.print_status_help(text.ends_with("--source")).await?;
+                return Ok(());
+            }
+            if text == "status --explain" {
+                self.ensure_help().await?;
+                self.update_status_explain().await?;
+                return Ok(());
+            }
+            if text == "buttons" {
+                self.execute_buttons("")?;
+                return Ok(());
+            }
+            if let Some(args) = text.strip_prefix("buttons ") {
+                self.execute_buttons(args)?;
                 return Ok(());
             }
             self.execute_command(text).await?;
@@ -252,6 +571,13 @@ impl Cli {
     fn set_current_devices(&mut self, text: &str) -> anyhow::Result<()> {
         self.current_device_indexes = self.parse_device_indexes(text)?;
         log::debug!("current_device_indexes={:?}", self.current_device_indexes);
+        self.switch_bot.set_concurrency_limit(
+            if self.num_current_devices() < self.args.parallel_threshold {
+                1
+            } else {
+                SwitchBot::DEFAULT_CONCURRENCY_LIMIT
+            },
+        );
         self.is_current_deivces_changed = true;
         Ok(())
     }
@@ -277,17 +603,44 @@ impl Cli {
         {
             return Ok(number - 1);
         }
+        if let Some((account, number)) = value.split_once(':')
+            && let Ok(number) = number.parse::<usize>()
+            && number > 0
+            && let Some(index) = self
+                .devices()
+                .iter()
+                .positions(|device| device.account() == account)
+                .nth(number - 1)
+        {
+            return Ok(index);
+        }
         self.devices()
             .index_by_device_id(value)
             .ok_or_else(|| anyhow::anyhow!("Not a valid device: \"{value}\""))
     }
 
+    /// The selector in the `account:number` form,
+    /// or `None` if the device is not in a named account.
+    fn account_selector(&self, index: usize) -> Option<String> {
+        let account = self.devices()[index].account();
+        if account.is_empty() {
+            return None;
+        }
+        let number = self.devices()[..index]
+            .iter()
+            .filter(|device| device.account() == account)
+            .count()
+            + 1;
+        Some(format!("{account}:{number}"))
+    }
+
     async fn execute_if_expr(&mut self, expr: &str) -> anyhow::Result<bool> {
         assert!(self.has_current_device());
         if let Some((condition, then_command, else_command)) = Self::parse_if_expr(expr) {
-            let (device, expr) = self.device_expr(condition);
+            let condition = Condition::new(condition)?;
+            let device = self.condition_device(&condition)?;
             device.update_status().await?;
-            let eval_result = device.eval_condition(expr)?;
+            let eval_result = device.evaluate(&condition)?;
             let command = if eval_result {
                 then_command
             } else {
@@ -317,13 +670,12 @@ impl Cli {
         None
     }
 
-    fn device_expr<'a>(&'a self, expr: &'a str) -> (&'a Device, &'a str) {
-        if let Some((device, expr)) = expr.split_once('.')
-            && let Ok(device_indexes) = self.parse_device_indexes(device)
-        {
-            return (&self.devices()[device_indexes[0]], expr);
+    fn condition_device(&self, condition: &Condition) -> anyhow::Result<&Device> {
+        if let Some(device) = condition.device() {
+            let device_indexes = self.parse_device_indexes(device)?;
+            return Ok(&self.devices()[device_indexes[0]]);
         }
-        (self.first_current_device(), expr)
+        Ok(self.first_current_device())
     }
 
     fn execute_global_builtin_command(&mut self, text: &str) -> anyhow::Result<bool> {
@@ -335,6 +687,16 @@ impl Cli {
             self.args.aliases.print();
             return Ok(true);
         }
+        if text == "keypad list-pending" {
+            Self::print_keypad_requests()?;
+            return Ok(true);
+        }
+        if let Some(args) = text.strip_prefix("history")
+            && (args.is_empty() || args.starts_with(' '))
+        {
+            self.print_history(args.trim())?;
+            return Ok(true);
+        }
         if let Some(rest) = text.strip_prefix("alias ") {
             let rest = rest.trim();
             if rest.is_empty() {
@@ -357,10 +719,14 @@ impl Cli {
             self.update_status(key).await?;
             return Ok(true);
         }
+        if let Some(args) = text.strip_prefix("keypad ") {
+            self.execute_keypad(args).await?;
+            return Ok(true);
+        }
         Ok(false)
     }
 
-    async fn execute_command(&self, text: &str) -> anyhow::Result<()> {
+    async fn execute_command(&mut self, text: &str) -> anyhow::Result<()> {
         assert!(self.has_current_device());
         if text.is_empty() {
             return Ok(());
@@ -369,73 +735,431 @@ impl Cli {
             return Ok(());
         }
         let command = CommandRequest::from(text);
-        self.for_each_selected_device(|device| device.command(&command), |_| Ok(()))
-            .await?;
+        let is_custom_button = command.command_type == CustomButtons::COMMAND_TYPE;
+        if is_custom_button {
+            self.check_custom_button(&command.command)?;
+        }
+        let device_ids = self.current_device_ids();
+        let report = if let Some(seconds) = self.args.wait {
+            self.switch_bot
+                .command_many_and_wait(&device_ids, &command, Duration::from_secs_f64(seconds))
+                .await
+                .map(Some)
+        } else if self.args.if_needed {
+            self.switch_bot
+                .command_many_if_needed(&device_ids, &command)
+                .await
+        } else {
+            self.switch_bot
+                .command_many(&device_ids, &command)
+                .await
+                .map(Some)
+        };
+        // Save the inferred status as soon as it changes,
+        // because the interactive mode and the MQTT bridge may not exit normally.
+        if zip(self.current_devices(), report.iter())
+            .any(|(device, (_, result))| device.is_status_inferred() && result.is_ok())
+        {
+            Args::save_shadow_state(&self.switch_bot);
+        }
+        if is_custom_button {
+            let succeeded: Vec<usize> = zip(self.current_device_indexes.clone(), report.iter())
+                .filter(|(_, (_, result))| result.is_ok())
+                .map(|(index, _)| index)
+                .collect();
+            for index in succeeded {
+                self.add_custom_button(index, &command.command);
+            }
+        }
+        self.handle_report(report, |device, response| {
+            match response {
+                Some(response) if !response.is_empty() => println!("{response:#}"),
+                Some(_) => {}
+                None => println!("Skipped {command} for {device}"),
+            }
+            Ok(())
+        })
+    }
+
+    /// Warn if the `button` is not registered but looks like a typo
+    /// of a registered button.
+    /// In the interactive mode, ask whether to send it.
+    fn check_custom_button(&self, button: &str) -> anyhow::Result<()> {
+        let Some(warning) = self.custom_button_warning(button) else {
+            return Ok(());
+        };
+        if self.command_source() != CommandSource::Interactive {
+            log::warn!("{warning}");
+            return Ok(());
+        }
+        let mut input = UserInput::new_with_prompt(&format!("{warning} Send it anyway? [y/N] "));
+        if !input.read_line()?.eq_ignore_ascii_case("y") {
+            anyhow::bail!(r#"Canceled "{button}""#);
+        }
+        Ok(())
+    }
+
+    /// The warning if the `button` is not registered but looks like a typo
+    /// of a registered button.
+    fn custom_button_warning(&self, button: &str) -> Option<String> {
+        let buttons = &self.args.custom_buttons;
+        self.current_devices().find_map(|device| {
+            let device_id = device.device_id();
+            if buttons.contains(device_id, button) {
+                return None;
+            }
+            buttons.suggest(device_id, button).map(|suggestion| {
+                format!(r#"Unknown button "{button}" for {device}; did you mean "{suggestion}"?"#)
+            })
+        })
+    }
+
+    /// Add the `button` to the [`CustomButtons`] of the device,
+    /// and create aliases such as `tv.netflix` for the aliases of the device.
+    fn add_custom_button(&mut self, index: usize, button: &str) {
+        let device_id = self.devices()[index].device_id().to_string();
+        if !self.args.custom_buttons.add(&device_id, button) {
+            return;
+        }
+        log::debug!("custom_button: added {button} to {device_id}");
+        let reverse_aliases = self.args.aliases.reverse_map();
+        let device_aliases: Vec<String> = [(index + 1).to_string().as_str(), &device_id]
+            .iter()
+            .filter_map(|selector| reverse_aliases.get(selector))
+            .flatten()
+            .map(|alias| alias.to_string())
+            .collect();
+        for alias in device_aliases {
+            self.args.aliases.insert_if_missing(
+                &format!("{alias}.{button}"),
+                &format!("{alias} {}/{button}", CustomButtons::COMMAND_TYPE),
+            );
+        }
+    }
+
+    /// The `buttons` built-in command to list, add, or remove [`CustomButtons`].
+    fn execute_buttons(&mut self, args: &str) -> anyhow::Result<()> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["add", buttons @ ..] if !buttons.is_empty() => {
+                for index in self.current_device_indexes.clone() {
+                    for button in buttons {
+                        self.add_custom_button(index, button);
+                    }
+                }
+            }
+            ["remove", buttons @ ..] if !buttons.is_empty() => {
+                for index in self.current_device_indexes.clone() {
+                    let device_id = self.devices()[index].device_id().to_string();
+                    for button in buttons {
+                        self.args.custom_buttons.remove(&device_id, button);
+                    }
+                }
+            }
+            _ => anyhow::bail!("Usage: buttons [add|remove <button>...]"),
+        }
+        for device in self.current_devices() {
+            let buttons = self.args.custom_buttons.get(device.device_id());
+            println!("{device}: {}", buttons.join(" "));
+        }
         Ok(())
     }
 
     async fn update_status(&self, key: &str) -> anyhow::Result<()> {
-        self.for_each_selected_device(
-            |device: &Device| device.update_status(),
-            |device| {
-                if key.is_empty() {
-                    device.write_status_to(stdout())?;
-                } else if let Some(value) = device.status_by_key(key) {
-                    println!("{value}");
-                } else {
-                    log::error!(r#"No status key "{key}" for {device}"#);
+        let report = self
+            .switch_bot
+            .update_status_many(&self.current_device_ids())
+            .await;
+        self.handle_report(report, |device, _| {
+            if key.is_empty() {
+                device.write_status_to(stdout())?;
+            } else if let Some(value) = device.status_by_key(key) {
+                println!("{value}");
+            } else {
+                log::error!(r#"No status key "{key}" for {device}"#);
+            }
+            Ok(())
+        })
+    }
+
+    async fn update_status_explain(&self) -> anyhow::Result<()> {
+        let help = self.help.as_ref().unwrap();
+        let report = self
+            .switch_bot
+            .update_status_many(&self.current_device_ids())
+            .await;
+        self.handle_report(report, |device, _| {
+            let mut keys = device.status_keys();
+            // Print in the order of the help, then the keys without help.
+            let status_helps = help.status_helps(device);
+            keys.sort_by_key(|key| {
+                status_helps
+                    .iter()
+                    .position(|status_help| status_help.key() == key)
+                    .unwrap_or(usize::MAX)
+            });
+            for key in keys {
+                let value = device.status_by_key(&key).unwrap();
+                println!("{key}: {value}");
+                if let Some(status_help) = help.status_help(device, &key) {
+                    println!(
+                        "    ({}) {}",
+                        status_help.summary(),
+                        status_help.description().text(Self::is_ansi())
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Pending keypad requests older than this are discarded,
+    /// because their results can't arrive without webhook receivers.
+    const KEYPAD_REQUEST_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+    const KEYPAD_USAGE: &str = "Usage: keypad add <name> <password> [<type>] [<duration>] | keypad remove <id> | keypad list-pending";
+
+    async fn execute_keypad(&self, args: &str) -> anyhow::Result<()> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let mut requests = Self::load_keypad_requests()?;
+        let mut results = Vec::new();
+        match words.as_slice() {
+            ["add", name, password, rest @ ..] => {
+                let passcode = Self::parse_passcode(name, password, rest)?;
+                for device in self.current_devices() {
+                    let result = match Keypad::new(device) {
+                        Ok(keypad) => keypad.create_key(&passcode).await,
+                        Err(error) => Err(error),
+                    };
+                    results.push((device.device_id().to_string(), result));
                 }
+            }
+            ["remove", id] => {
+                let id: u64 = id.parse()?;
+                for device in self.current_devices() {
+                    let result = match Keypad::new(device) {
+                        Ok(keypad) => keypad.delete_key(id).await,
+                        Err(error) => Err(error),
+                    };
+                    results.push((device.device_id().to_string(), result));
+                }
+            }
+            ["list-pending"] => {}
+            _ => anyhow::bail!(Self::KEYPAD_USAGE),
+        }
+        // Save the requests of the succeeded devices even if others failed.
+        let report: BatchReport<KeypadRequest> = results.into_iter().collect();
+        for (_, result) in report.iter() {
+            if let Ok(request) = result {
+                requests.push(request.clone());
+            }
+        }
+        Self::save_keypad_requests(&requests)?;
+        if !report.is_empty() {
+            self.handle_report(report, |_, request| {
+                println!("Requested: {request}");
                 Ok(())
-            },
-        )
-        .await?;
+            })?;
+        }
+        Self::print_keypad_requests_of(&requests);
         Ok(())
     }
 
-    async fn for_each_selected_device<'a, 'b, FnAsync, Fut>(
-        &'a self,
-        fn_async: FnAsync,
-        fn_post: impl Fn(&Device) -> anyhow::Result<()>,
-    ) -> anyhow::Result<()>
-    where
-        FnAsync: Fn(&'a Device) -> Fut + Send + Sync,
-        Fut: Future<Output = anyhow::Result<()>> + Send + 'b,
-    {
-        assert!(self.has_current_device());
+    /// Parse `[<type>] [<duration>]` of the `keypad add` command.
+    /// The type is `timeLimit` if only the duration is specified.
+    fn parse_passcode(name: &str, password: &str, rest: &[&str]) -> anyhow::Result<Passcode> {
+        let (passcode_type, duration) = match rest {
+            [] => (PasscodeType::Permanent, None),
+            [duration] if duration.starts_with(|c: char| c.is_ascii_digit()) => {
+                (PasscodeType::TimeLimit, Some(*duration))
+            }
+            [passcode_type] => (passcode_type.parse()?, None),
+            [passcode_type, duration] => (passcode_type.parse()?, Some(*duration)),
+            _ => anyhow::bail!(Self::KEYPAD_USAGE),
+        };
+        let passcode = match (passcode_type, duration) {
+            (PasscodeType::Permanent, None) => Passcode::permanent(name, password),
+            (PasscodeType::Urgent, None) => Passcode::urgent(name, password),
+            (PasscodeType::TimeLimit, Some(duration)) => {
+                Passcode::time_limit(name, password, Self::parse_time_window(duration)?)
+            }
+            (PasscodeType::Disposable, Some(duration)) => {
+                Passcode::disposable(name, password, Self::parse_time_window(duration)?)
+            }
+            (passcode_type, None) => {
+                anyhow::bail!("The passcode type {passcode_type} requires a duration")
+            }
+            (passcode_type, Some(_)) => {
+                anyhow::bail!("The passcode type {passcode_type} can't have a duration")
+            }
+        };
+        passcode.validate()?;
+        Ok(passcode)
+    }
+
+    /// Parse a duration such as `90s`, `30m`, `2h`, or `7d` into a [`TimeWindow`] from now.
+    fn parse_time_window(text: &str) -> anyhow::Result<TimeWindow> {
+        TimeWindow::from_now(Self::parse_duration(text)?)
+    }
+
+    /// Parse a duration such as `90s`, `30m`, `2h`, or `7d`.
+    fn parse_duration(text: &str) -> anyhow::Result<Duration> {
+        let unit_len = text.len() - text.trim_end_matches(char::is_alphabetic).len();
+        let (number, unit) = text.split_at(text.len() - unit_len);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| anyhow::anyhow!(r#"Not a valid duration: "{text}""#))?;
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => anyhow::bail!(r#"The duration unit must be s, m, h, or d: "{text}""#),
+        };
+        let Some(seconds) = number.checked_mul(seconds) else {
+            anyhow::bail!(r#"The duration is too long: "{text}""#);
+        };
+        Ok(Duration::from_secs(seconds))
+    }
 
-        let results = if self.num_current_devices() < self.args.parallel_threshold {
-            log::debug!("for_each: sequential ({})", self.num_current_devices());
-            let mut results = Vec::with_capacity(self.num_current_devices());
-            for device in self.current_devices() {
-                results.push(fn_async(device).await);
+    /// The `history` built-in command to print the audit log.
+    fn print_history(&self, args: &str) -> anyhow::Result<()> {
+        let (device, range) = match args.rsplit_once(' ') {
+            Some((device, range)) if Self::parse_history_range(range).is_ok() => {
+                (device.trim(), range)
             }
-            results
+            _ if Self::parse_history_range(args).is_ok() => ("", args),
+            _ => (args, ""),
+        };
+        let (since, until) = Self::parse_history_range(range)?;
+        let device_ids: Vec<&str> = if !device.is_empty() {
+            match self.parse_device_indexes(device) {
+                Ok(indexes) => indexes
+                    .iter()
+                    .map(|&index| self.devices()[index].device_id())
+                    .collect(),
+                Err(_) => vec![device],
+            }
+        } else if self.has_current_device() {
+            self.current_device_ids()
         } else {
-            log::debug!("for_each: parallel ({})", self.num_current_devices());
-            let (_, join_results) = async_scoped::TokioScope::scope_and_block(|s| {
-                for device in self.current_devices() {
-                    s.spawn(fn_async(device));
-                }
-            });
-            join_results
-                .into_iter()
-                .map(|result| result.unwrap_or_else(|error| Err(error.into())))
-                .collect()
+            Vec::new()
         };
+        let records = AuditLog::read(Args::audit_log_path()?)?;
+        for record in records
+            .iter()
+            .filter(|record| Self::history_matches(record, &device_ids, since, until))
+        {
+            println!("{record}");
+        }
+        Ok(())
+    }
+
+    /// Parse the time range `<since>[..<until>]` of the `history` command,
+    /// such as `12h..6h` for from 12 hours ago to 6 hours ago.
+    fn parse_history_range(text: &str) -> anyhow::Result<(Option<SystemTime>, Option<SystemTime>)> {
+        if text.is_empty() {
+            return Ok((None, None));
+        }
+        let now = SystemTime::now();
+        let ago = |text: &str| -> anyhow::Result<SystemTime> {
+            Ok(now
+                .checked_sub(Self::parse_duration(text)?)
+                .unwrap_or(SystemTime::UNIX_EPOCH))
+        };
+        Ok(match text.split_once("..") {
+            Some((since, until)) => (Some(ago(since)?), Some(ago(until)?)),
+            None => (Some(ago(text)?), None),
+        })
+    }
+
+    /// True if the `record` is of the `device_ids` (or their names),
+    /// and in the time range. Empty `device_ids` matches all devices.
+    fn history_matches(
+        record: &AuditRecord,
+        device_ids: &[&str],
+        since: Option<SystemTime>,
+        until: Option<SystemTime>,
+    ) -> bool {
+        (device_ids.is_empty()
+            || device_ids.iter().any(|device| {
+                record.device_id() == *device || record.device_name().eq_ignore_ascii_case(device)
+            }))
+            && since.is_none_or(|since| record.timestamp() >= since)
+            && until.is_none_or(|until| record.timestamp() <= until)
+    }
 
-        let last_error_index = results.iter().rposition(|result| result.is_err());
-        for (i, (device, result)) in zip(self.current_devices(), results).enumerate() {
+    fn load_keypad_requests() -> anyhow::Result<KeypadRequests> {
+        let path = Args::keypad_requests_path()?;
+        if !path.exists() {
+            return Ok(KeypadRequests::default());
+        }
+        let mut requests: KeypadRequests = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        requests.remove_older_than(Self::KEYPAD_REQUEST_MAX_AGE);
+        Ok(requests)
+    }
+
+    fn save_keypad_requests(requests: &KeypadRequests) -> anyhow::Result<()> {
+        let path = Args::keypad_requests_path()?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, serde_json::to_string(requests)?)?;
+        Ok(())
+    }
+
+    fn print_keypad_requests() -> anyhow::Result<()> {
+        Self::print_keypad_requests_of(&Self::load_keypad_requests()?);
+        Ok(())
+    }
+
+    fn print_keypad_requests_of(requests: &KeypadRequests) {
+        if requests.pending().is_empty() {
+            return;
+        }
+        println!("Pending keypad requests:");
+        let now = SystemTime::now();
+        for request in requests.pending() {
+            let age = now
+                .duration_since(request.requested_at())
+                .unwrap_or_default()
+                .as_secs();
+            println!("  {request}, {}m ago", age / 60);
+        }
+    }
+
+    fn current_device_ids(&self) -> Vec<&str> {
+        self.current_devices().map(Device::device_id).collect()
+    }
+
+    /// Call `fn_post` for each device that succeeded.
+    /// If any of multiple devices failed,
+    /// print the results of all devices and return an error.
+    fn handle_report<T>(
+        &self,
+        report: BatchReport<T>,
+        fn_post: impl Fn(&Device, &T) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        assert_eq!(report.len(), self.num_current_devices());
+        for (device, (_, result)) in zip(self.current_devices(), report.iter()) {
+            if let Ok(value) = result {
+                fn_post(device, value)?;
+            }
+        }
+        if report.is_all_succeeded() {
+            return Ok(());
+        }
+        if report.len() == 1 {
+            let (_, result) = report.into_iter().next().unwrap();
+            return result.map(|_| ());
+        }
+        for ((index, device), (_, result)) in zip(self.current_devices_with_index(), report.iter())
+        {
             match result {
-                Ok(_) => fn_post(device)?,
-                Err(error) => {
-                    if i == last_error_index.unwrap() {
-                        return Err(error);
-                    }
-                    log::error!("{error}");
-                }
+                Ok(_) => println!("{}: {device}: OK", index + 1),
+                Err(error) => println!("{}: {device}: {error}", index + 1),
             }
         }
-        Ok(())
+        anyhow::bail!("{} of {} devices failed", report.num_failed(), report.len())
     }
 }
 
@@ -473,6 +1197,68 @@ mod tests {
         assert_eq!(cli.parse_device_indexes("1,j,5").unwrap(), vec![0, 1, 2, 4]);
     }
 
+    #[test]
+    fn add_custom_button() {
+        let mut cli = Cli::new_for_test(3);
+        cli.args.aliases.insert("tv".into(), "2".into());
+        cli.add_custom_button(1, "netflix");
+        let device_id = cli.devices()[1].device_id().to_string();
+        assert!(cli.args.custom_buttons.contains(&device_id, "netflix"));
+        assert_eq!(
+            cli.args.aliases.get("tv.netflix").map(String::as_str),
+            Some("tv customize/netflix")
+        );
+        assert!(cli.custom_button_warning("netflix").is_none());
+
+        cli.current_device_indexes = vec![1];
+        assert!(cli.custom_button_warning("netflx").is_some());
+        assert!(cli.custom_button_warning("hulu").is_none());
+    }
+
+    #[test]
+    fn history_matches() -> anyhow::Result<()> {
+        let record: AuditRecord = serde_json::from_value(serde_json::json!({
+            "timestamp": SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)?
+                .as_secs() - 2 * 60 * 60,
+            "deviceId": "D1",
+            "deviceName": "Heater",
+            "command": {"command": "turnOn"},
+            "source": "batch",
+            "latencyMs": 100,
+        }))?;
+        let matches = |device_ids: &[&str], range: &str| -> anyhow::Result<bool> {
+            let (since, until) = Cli::parse_history_range(range)?;
+            Ok(Cli::history_matches(&record, device_ids, since, until))
+        };
+        assert!(matches(&[], "")?);
+        assert!(matches(&["D1"], "3h")?);
+        assert!(matches(&["heater"], "3h..1h")?);
+        assert!(!matches(&["D2"], "")?);
+        assert!(!matches(&[], "1h")?);
+        assert!(!matches(&[], "4h..3h")?);
+        assert!(Cli::parse_history_range("3x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_device_indexes_account() {
+        let cli = Cli {
+            switch_bot: SwitchBot::new_for_test_with_accounts(&[("home", 3), ("office", 2)]),
+            ..Default::default()
+        };
+        assert_eq!(cli.parse_device_indexes("home:2").unwrap(), vec![1]);
+        assert_eq!(cli.parse_device_indexes("office:2").unwrap(), vec![4]);
+        assert_eq!(cli.parse_device_indexes("office:1,2").unwrap(), vec![3, 1]);
+        assert!(cli.parse_device_indexes("office:3").is_err());
+        assert!(cli.parse_device_indexes("office:0").is_err());
+        assert!(cli.parse_device_indexes("other:1").is_err());
+
+        assert_eq!(cli.account_selector(0).unwrap(), "home:1");
+        assert_eq!(cli.account_selector(4).unwrap(), "office:2");
+        assert_eq!(Cli::new_for_test(1).account_selector(0), None);
+    }
+
     #[test]
     fn parse_if_expr() {
         assert_eq!(Cli::parse_if_expr(""), None);
@@ -489,6 +1275,60 @@ mod tests {
         assert_eq!(Cli::parse_if_expr("ifXaXbXc"), None);
     }
 
+    #[test]
+    fn condition_device() -> anyhow::Result<()> {
+        let mut cli = Cli::new_for_test(10);
+        cli.set_current_devices("2")?;
+        let device = cli.condition_device(&Condition::new("power=on")?)?;
+        assert_eq!(device.device_id(), "device2");
+        let device = cli.condition_device(&Condition::new("8.power=on")?)?;
+        assert_eq!(device.device_id(), "device8");
+        cli.args.aliases.insert("k".into(), "3,5".into());
+        let device = cli.condition_device(&Condition::new("k.power=on")?)?;
+        assert_eq!(device.device_id(), "device3");
+        assert!(
+            cli.condition_device(&Condition::new("99.power=on")?)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_passcode() -> anyhow::Result<()> {
+        let passcode = Cli::parse_passcode("a", "123456", &[])?;
+        assert_eq!(passcode.passcode_type(), PasscodeType::Permanent);
+        let passcode = Cli::parse_passcode("a", "123456", &["urgent"])?;
+        assert_eq!(passcode.passcode_type(), PasscodeType::Urgent);
+        let passcode = Cli::parse_passcode("a", "123456", &["2h"])?;
+        assert_eq!(passcode.passcode_type(), PasscodeType::TimeLimit);
+        let window = passcode.time_window().unwrap();
+        assert_eq!(
+            window.end().duration_since(window.start())?,
+            Duration::from_secs(2 * 60 * 60)
+        );
+        let passcode = Cli::parse_passcode("a", "123456", &["disposable", "30m"])?;
+        assert_eq!(passcode.passcode_type(), PasscodeType::Disposable);
+        assert!(Cli::parse_passcode("a", "123456", &["timeLimit"]).is_err());
+        assert!(Cli::parse_passcode("a", "123456", &["permanent", "1d"]).is_err());
+        assert!(Cli::parse_passcode("a", "123456", &["1y"]).is_err());
+        assert!(Cli::parse_passcode("a", "123456", &["200000000000000d"]).is_err());
+        assert!(Cli::parse_passcode("a", "123456", &["18446744073709551615d"]).is_err());
+        assert!(Cli::parse_passcode("a", "12", &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn is_offline_command() {
+        let mut cli = Cli::new_for_test(0);
+        assert!(cli.is_offline_command("help type:Bot"));
+        assert!(cli.is_offline_command("help ir:TV"));
+        assert!(!cli.is_offline_command("help"));
+        assert!(!cli.is_offline_command("1 help"));
+        assert!(cli.is_offline_command("keypad list-pending"));
+        cli.args.aliases.insert("tv".into(), "help ir:TV".into());
+        assert!(cli.is_offline_command("tv"));
+    }
+
     #[test]
     fn command_alias() {
         let mut cli = Cli::new_for_test(10);
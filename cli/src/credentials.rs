@@ -0,0 +1,350 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, Generate},
+};
+use switchbot_api::{CredentialProvider, Credentials};
+
+/// Where to get the [`Credentials`] from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum CredentialSource {
+    /// The plaintext `token` and `secret` in the config file.
+    #[default]
+    Config,
+    /// The `SWITCHBOT_TOKEN` and `SWITCHBOT_SECRET` environment variables.
+    Env,
+    /// A JSON file readable only by the owner.
+    File(PathBuf),
+    /// The stdout of an external command.
+    Command(String),
+    /// The passphrase-encrypted credentials in the config file.
+    Encrypted,
+}
+
+impl FromStr for CredentialSource {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "config" => return Ok(Self::Config),
+            "env" => return Ok(Self::Env),
+            "encrypted" => return Ok(Self::Encrypted),
+            _ => {}
+        }
+        if let Some(path) = value.strip_prefix("file:") {
+            return Ok(Self::File(path.into()));
+        }
+        if let Some(command) = value.strip_prefix("command:") {
+            return Ok(Self::Command(command.into()));
+        }
+        anyhow::bail!(r#"Unknown credential source "{value}""#)
+    }
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config => write!(f, "config"),
+            Self::Env => write!(f, "env"),
+            Self::File(path) => write!(f, "file:{}", path.display()),
+            Self::Command(command) => write!(f, "command:{command}"),
+            Self::Encrypted => write!(f, "encrypted"),
+        }
+    }
+}
+
+/// Reads the `SWITCHBOT_TOKEN` and `SWITCHBOT_SECRET` environment variables.
+pub(crate) struct EnvCredentialProvider;
+
+impl EnvCredentialProvider {
+    pub const TOKEN_VAR: &str = "SWITCHBOT_TOKEN";
+    pub const SECRET_VAR: &str = "SWITCHBOT_SECRET";
+}
+
+impl CredentialProvider for EnvCredentialProvider {
+    fn credentials(&self) -> anyhow::Result<Credentials> {
+        let var = |name: &str| {
+            env::var(name)
+                .map_err(|_| anyhow::anyhow!("The environment variable {name} is not set"))
+        };
+        Ok(Credentials::new(
+            var(Self::TOKEN_VAR)?,
+            var(Self::SECRET_VAR)?,
+        ))
+    }
+}
+
+/// Reads a JSON file in the form of `{"token": "...", "secret": "..."}`.
+///
+/// The file must be readable only by the owner on Unix.
+pub(crate) struct FileCredentialProvider {
+    path: PathBuf,
+}
+
+#[derive(serde::Deserialize)]
+struct CredentialsFile {
+    token: String,
+    secret: String,
+}
+
+impl FileCredentialProvider {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.into() }
+    }
+
+    #[cfg(unix)]
+    fn ensure_owner_only(&self) -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&self.path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            anyhow::bail!(
+                "{} must be readable only by the owner (mode {:o}); try `chmod 600`",
+                self.path.display(),
+                mode & 0o777
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn ensure_owner_only(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl CredentialProvider for FileCredentialProvider {
+    fn credentials(&self) -> anyhow::Result<Credentials> {
+        self.ensure_owner_only()?;
+        let json = fs::read_to_string(&self.path)?;
+        let file: CredentialsFile = serde_json::from_str(&json)
+            .map_err(|error| anyhow::anyhow!("{}: {error}", self.path.display()))?;
+        Ok(Credentials::new(file.token, file.secret))
+    }
+}
+
+/// Runs an external command, such as password managers,
+/// and reads the token from the first line of its stdout,
+/// and the secret from the second line.
+pub(crate) struct CommandCredentialProvider {
+    command: String,
+}
+
+impl CommandCredentialProvider {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    fn shell_command(&self) -> Command {
+        if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(&self.command);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.command);
+            command
+        }
+    }
+}
+
+/// Parse the token from the first non-empty line,
+/// and the secret from the second non-empty line.
+fn parse_credential_lines(text: &str) -> Option<Credentials> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    Some(Credentials::new(lines.next()?, lines.next()?))
+}
+
+impl CredentialProvider for CommandCredentialProvider {
+    fn credentials(&self) -> anyhow::Result<Credentials> {
+        log::debug!("credential command: {}", self.command);
+        let output = self.shell_command().output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "The credential command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        parse_credential_lines(&String::from_utf8(output.stdout)?).ok_or_else(|| {
+            anyhow::anyhow!("The credential command must print the token and the secret")
+        })
+    }
+}
+
+/// Decrypts the [`Credentials`] encrypted by a passphrase.
+///
+/// The passphrase is read from the `SWITCHBOT_PASSPHRASE` environment variable,
+/// or prompted if it's not set.
+pub(crate) struct EncryptedCredentialProvider {
+    encrypted: String,
+    passphrase: Option<String>,
+}
+
+impl EncryptedCredentialProvider {
+    const PASSPHRASE_VAR: &str = "SWITCHBOT_PASSPHRASE";
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = 12;
+    const PBKDF2_ROUNDS: u32 = if cfg!(test) { 1_000 } else { 600_000 };
+
+    pub fn new(encrypted: &str) -> Self {
+        Self {
+            encrypted: encrypted.into(),
+            passphrase: None,
+        }
+    }
+
+    /// Use the `passphrase` instead of reading it,
+    /// such as when decrypting multiple accounts.
+    pub fn with_passphrase(mut self, passphrase: Option<&str>) -> Self {
+        self.passphrase = passphrase.map(Into::into);
+        self
+    }
+
+    /// Read the passphrase from the environment variable,
+    /// or from the terminal without echoing it.
+    pub fn read_passphrase(prompt: &str) -> anyhow::Result<String> {
+        if let Ok(passphrase) = env::var(Self::PASSPHRASE_VAR) {
+            return Ok(passphrase);
+        }
+        Ok(rpassword::prompt_password(prompt)?)
+    }
+
+    /// Read a new passphrase, and read it again to confirm it's not mistyped.
+    pub fn read_new_passphrase() -> anyhow::Result<String> {
+        let passphrase = Self::read_passphrase("New passphrase> ")?;
+        if passphrase.is_empty() {
+            anyhow::bail!("The passphrase is empty");
+        }
+        if Self::read_passphrase("Confirm new passphrase> ")? != passphrase {
+            anyhow::bail!("The passphrases don't match");
+        }
+        Ok(passphrase)
+    }
+
+    /// Encrypt the `credentials` by the `passphrase`,
+    /// and return it in the Base64 encoding.
+    pub fn encrypt(credentials: &Credentials, passphrase: &str) -> anyhow::Result<String> {
+        let mut salt = [0u8; Self::SALT_LEN];
+        getrandom::fill(&mut salt).map_err(|error| anyhow::anyhow!("{error}"))?;
+        let nonce = Nonce::generate();
+        let plaintext = format!("{}\n{}", credentials.token(), credentials.secret());
+        let ciphertext = Self::cipher(passphrase, &salt)
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the credentials"))?;
+
+        let mut data = Vec::with_capacity(salt.len() + nonce.len() + ciphertext.len());
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(data))
+    }
+
+    fn decrypt(&self, passphrase: &str) -> anyhow::Result<Credentials> {
+        let data = STANDARD.decode(&self.encrypted)?;
+        if data.len() < Self::SALT_LEN + Self::NONCE_LEN {
+            anyhow::bail!("The encrypted credentials are broken");
+        }
+        let (salt, data) = data.split_at(Self::SALT_LEN);
+        let (nonce, ciphertext) = data.split_at(Self::NONCE_LEN);
+        let nonce = Nonce::try_from(nonce)?;
+        let plaintext = Self::cipher(passphrase, salt)
+            .decrypt(&nonce, ciphertext)
+            .map_err(|_| anyhow::anyhow!("Wrong passphrase"))?;
+        parse_credential_lines(&String::from_utf8(plaintext)?)
+            .ok_or_else(|| anyhow::anyhow!("The encrypted credentials are broken"))
+    }
+
+    fn cipher(passphrase: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+        let key: [u8; 32] = pbkdf2::pbkdf2_hmac_array::<pbkdf2::sha2::Sha256, 32>(
+            passphrase.as_bytes(),
+            salt,
+            Self::PBKDF2_ROUNDS,
+        );
+        ChaCha20Poly1305::new(&Key::from(key))
+    }
+}
+
+impl CredentialProvider for EncryptedCredentialProvider {
+    fn credentials(&self) -> anyhow::Result<Credentials> {
+        if self.encrypted.is_empty() {
+            anyhow::bail!("No encrypted credentials; please run with `--encrypt` first");
+        }
+        match &self.passphrase {
+            Some(passphrase) => self.decrypt(passphrase),
+            None => self.decrypt(&Self::read_passphrase("Passphrase> ")?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_from_str() -> anyhow::Result<()> {
+        for text in [
+            "config",
+            "env",
+            "encrypted",
+            "file:/a/b.json",
+            "command:pass show sb",
+        ] {
+            let source: CredentialSource = text.parse()?;
+            assert_eq!(source.to_string(), text);
+        }
+        assert_eq!(
+            "command:a:b".parse::<CredentialSource>()?,
+            CredentialSource::Command("a:b".into())
+        );
+        assert!("unknown".parse::<CredentialSource>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_credential_lines() {
+        assert_eq!(
+            super::parse_credential_lines("token\nsecret\n"),
+            Some(Credentials::new("token", "secret"))
+        );
+        assert_eq!(
+            super::parse_credential_lines("\n token \r\n secret\n"),
+            Some(Credentials::new("token", "secret"))
+        );
+        assert_eq!(super::parse_credential_lines("token\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_provider() -> anyhow::Result<()> {
+        let provider = CommandCredentialProvider::new("printf 'token\\nsecret\\n'");
+        assert_eq!(provider.credentials()?, Credentials::new("token", "secret"));
+        assert!(
+            CommandCredentialProvider::new("exit 1")
+                .credentials()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encrypt() -> anyhow::Result<()> {
+        let credentials = Credentials::new("my_token", "my_secret");
+        let encrypted = EncryptedCredentialProvider::encrypt(&credentials, "pass")?;
+        assert!(!encrypted.contains("my_token"));
+        let provider = EncryptedCredentialProvider::new(&encrypted);
+        assert_eq!(provider.decrypt("pass")?, credentials);
+        assert!(provider.decrypt("wrong").is_err());
+        let provider = provider.with_passphrase(Some("pass"));
+        assert_eq!(provider.credentials()?, credentials);
+        Ok(())
+    }
+}
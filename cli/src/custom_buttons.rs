@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+/// The custom buttons of infrared remote devices by their device IDs.
+///
+/// The `customize` command type requires the exact button names
+/// learned in the SwitchBot app, but the SwitchBot API can't list them.
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub(crate) struct CustomButtons(HashMap<String, Vec<String>>);
+
+impl CustomButtons {
+    pub const COMMAND_TYPE: &str = "customize";
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The buttons of the device, in the order they were added.
+    pub fn get(&self, device_id: &str) -> &[String] {
+        self.0.get(device_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn contains(&self, device_id: &str, button: &str) -> bool {
+        self.get(device_id).iter().any(|b| b == button)
+    }
+
+    /// Add a button. Returns `true` if it's added, `false` if it exists.
+    pub fn add(&mut self, device_id: &str, button: &str) -> bool {
+        if button.is_empty() || self.contains(device_id, button) {
+            return false;
+        }
+        self.0
+            .entry(device_id.into())
+            .or_default()
+            .push(button.into());
+        true
+    }
+
+    /// Remove a button. Returns `true` if it's removed.
+    pub fn remove(&mut self, device_id: &str, button: &str) -> bool {
+        let Some(buttons) = self.0.get_mut(device_id) else {
+            return false;
+        };
+        let len = buttons.len();
+        buttons.retain(|b| b != button);
+        let is_removed = buttons.len() != len;
+        if buttons.is_empty() {
+            self.0.remove(device_id);
+        }
+        is_removed
+    }
+
+    /// The closest button to the `button`, if there's one close enough
+    /// to be a typo.
+    pub fn suggest(&self, device_id: &str, button: &str) -> Option<&str> {
+        let button = button.to_lowercase();
+        let max_distance = (button.chars().count() / 3).max(1);
+        self.get(device_id)
+            .iter()
+            .map(|b| (edit_distance(&b.to_lowercase(), &button), b))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, b)| b.as_str())
+    }
+}
+
+/// The Levenshtein distance of two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(row[j]).min(current) + 1
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_remove() {
+        let mut buttons = CustomButtons::default();
+        assert!(buttons.add("tv", "netflix"));
+        assert!(buttons.add("tv", "hulu"));
+        assert!(!buttons.add("tv", "netflix"));
+        assert_eq!(buttons.get("tv"), ["netflix", "hulu"]);
+        assert!(buttons.get("ac").is_empty());
+
+        assert!(buttons.remove("tv", "netflix"));
+        assert!(!buttons.remove("tv", "netflix"));
+        assert!(buttons.remove("tv", "hulu"));
+        assert!(buttons.is_empty());
+    }
+
+    #[test]
+    fn suggest() {
+        let mut buttons = CustomButtons::default();
+        buttons.add("tv", "netflix");
+        buttons.add("tv", "input");
+        assert_eq!(buttons.suggest("tv", "netflx"), Some("netflix"));
+        assert_eq!(buttons.suggest("tv", "Netflix"), Some("netflix"));
+        assert_eq!(buttons.suggest("tv", "inpt"), Some("input"));
+        assert_eq!(buttons.suggest("tv", "volume"), None);
+        assert_eq!(buttons.suggest("ac", "netflix"), None);
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("", "abc"), 3);
+        assert_eq!(super::edit_distance("abc", "abc"), 0);
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+    }
+}
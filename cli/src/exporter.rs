@@ -0,0 +1,265 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use switchbot_api::{ApiStats, Device, EndpointStats};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Serves the numeric status of devices and the [`ApiStats`]
+/// in the [Prometheus text format] at `/metrics`.
+///
+/// [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+#[derive(Debug, Default)]
+pub(crate) struct Exporter {
+    metrics: Arc<RwLock<String>>,
+}
+
+impl Exporter {
+    /// The [SwitchBot API] allows 10,000 requests a day.
+    /// Use up to a half of it, leaving the rest for commands.
+    ///
+    /// [SwitchBot API]: https://github.com/OpenWonderLabs/SwitchBotAPI
+    const DAILY_REQUEST_BUDGET: u64 = 5_000;
+    const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// The interval to refresh the status of `num_devices` devices
+    /// within the [`Exporter::DAILY_REQUEST_BUDGET`].
+    pub fn refresh_interval(num_devices: usize) -> Duration {
+        let seconds = (num_devices as u64 * 24 * 60 * 60).div_ceil(Self::DAILY_REQUEST_BUDGET);
+        Duration::from_secs(seconds).max(Self::MIN_REFRESH_INTERVAL)
+    }
+
+    /// Parse the address to listen to.
+    /// A port number listens to the localhost.
+    pub fn parse_address(address: &str) -> String {
+        if address.parse::<u16>().is_ok() {
+            return format!("127.0.0.1:{address}");
+        }
+        address.into()
+    }
+
+    /// Start serving the metrics set by [`Exporter::set_metrics()`].
+    pub async fn listen(&self, address: &str) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        println!(
+            "Serving metrics at http://{}/metrics",
+            listener.local_addr()?
+        );
+        let metrics = self.metrics.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let metrics = metrics.clone();
+                        tokio::spawn(async move {
+                            if let Err(error) = Self::respond(stream, &metrics).await {
+                                log::debug!("exporter: {error}");
+                            }
+                        });
+                    }
+                    Err(error) => log::error!("exporter: {error}"),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub fn set_metrics(&self, metrics: String) {
+        *self.metrics.write().unwrap() = metrics;
+    }
+
+    async fn respond(mut stream: TcpStream, metrics: &RwLock<String>) -> anyhow::Result<()> {
+        let mut buffer = vec![0; 8192];
+        let mut len = 0;
+        while !buffer[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+            if len == buffer.len() {
+                anyhow::bail!("The request header is too large");
+            }
+            let read = stream.read(&mut buffer[len..]).await?;
+            if read == 0 {
+                break;
+            }
+            len += read;
+        }
+        let request = String::from_utf8_lossy(&buffer[..len]);
+        let request_line = request.lines().next().unwrap_or_default();
+        log::debug!("exporter: {request_line}");
+        let mut words = request_line.split_whitespace();
+        let response = match (words.next(), words.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                let body = metrics.read().unwrap().clone();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into(),
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// Render the metrics of the `devices` and the `api_stats`.
+    /// The `bool` of the `devices` is whether its status is updated successfully.
+    pub fn render(devices: &[(&Device, bool)], api_stats: &ApiStats) -> String {
+        let mut text = String::new();
+        let mut gauges: BTreeMap<String, Vec<(String, f64)>> = BTreeMap::new();
+        for (device, is_up) in devices {
+            let labels = Self::device_labels(device);
+            gauges
+                .entry("device_up".into())
+                .or_default()
+                .push((labels.clone(), if *is_up { 1.0 } else { 0.0 }));
+            if !is_up {
+                continue;
+            }
+            for key in device.status_keys() {
+                if let Some(value) = device.status_by_key(&key).and_then(|value| value.as_f64()) {
+                    gauges
+                        .entry(Self::metric_name(&key))
+                        .or_default()
+                        .push((labels.clone(), value));
+                }
+            }
+        }
+        for (name, samples) in &gauges {
+            if name == "device_up" {
+                writeln!(text, "# HELP switchbot_{name} 1 if the status is updated.").unwrap();
+            }
+            writeln!(text, "# TYPE switchbot_{name} gauge").unwrap();
+            for (labels, value) in samples {
+                writeln!(text, "switchbot_{name}{{{labels}}} {value}").unwrap();
+            }
+        }
+
+        let endpoints: Vec<(String, EndpointStats)> = api_stats
+            .endpoints()
+            .into_iter()
+            .map(|(endpoint, stats)| (format!(r#"endpoint="{}""#, Self::escape(&endpoint)), stats))
+            .collect();
+        writeln!(text, "# TYPE switchbot_api_requests_total counter").unwrap();
+        for (labels, stats) in &endpoints {
+            writeln!(
+                text,
+                "switchbot_api_requests_total{{{labels}}} {}",
+                stats.count()
+            )
+            .unwrap();
+        }
+        writeln!(text, "# TYPE switchbot_api_errors_total counter").unwrap();
+        for (labels, stats) in &endpoints {
+            writeln!(
+                text,
+                "switchbot_api_errors_total{{{labels}}} {}",
+                stats.errors()
+            )
+            .unwrap();
+        }
+        writeln!(
+            text,
+            "# TYPE switchbot_api_request_duration_seconds summary"
+        )
+        .unwrap();
+        for (labels, stats) in &endpoints {
+            let name = "switchbot_api_request_duration_seconds";
+            let sum = stats.total_latency().as_secs_f64();
+            writeln!(text, "{name}_sum{{{labels}}} {sum}").unwrap();
+            writeln!(text, "{name}_count{{{labels}}} {}", stats.count()).unwrap();
+        }
+        text
+    }
+
+    fn device_labels(device: &Device) -> String {
+        format!(
+            r#"device_id="{}",device_name="{}",device_type="{}""#,
+            Self::escape(device.device_id()),
+            Self::escape(device.device_name()),
+            Self::escape(device.device_type_or_remote_type())
+        )
+    }
+
+    /// Convert a status key such as `electricityOfDay`
+    /// to a metric name such as `electricity_of_day`.
+    fn metric_name(key: &str) -> String {
+        let mut name = String::with_capacity(key.len() + 4);
+        let mut prev: Option<char> = None;
+        for ch in key.chars() {
+            if ch.is_ascii_uppercase()
+                && prev.is_some_and(|prev| prev.is_ascii_lowercase() || prev.is_ascii_digit())
+            {
+                name.push('_');
+            }
+            name.push(if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_lowercase()
+            } else {
+                '_'
+            });
+            prev = Some(ch);
+        }
+        name
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('\\', r"\\")
+            .replace('"', r#"\""#)
+            .replace('\n', r"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_name() {
+        assert_eq!(Exporter::metric_name("temperature"), "temperature");
+        assert_eq!(Exporter::metric_name("CO2"), "co2");
+        assert_eq!(
+            Exporter::metric_name("electricityOfDay"),
+            "electricity_of_day"
+        );
+        assert_eq!(Exporter::metric_name("lightLevel"), "light_level");
+    }
+
+    #[test]
+    fn refresh_interval() {
+        assert_eq!(Exporter::refresh_interval(1), Duration::from_secs(60));
+        // 20 devices * 86400 / 5000 = 345.6 seconds.
+        assert_eq!(Exporter::refresh_interval(20), Duration::from_secs(346));
+    }
+
+    #[test]
+    fn parse_address() {
+        assert_eq!(Exporter::parse_address("9110"), "127.0.0.1:9110");
+        assert_eq!(Exporter::parse_address("0.0.0.0:9110"), "0.0.0.0:9110");
+    }
+
+    #[test]
+    fn render() {
+        let switch_bot = switchbot_api::SwitchBot::new_for_test(2);
+        let devices = switch_bot.devices();
+        let text = Exporter::render(
+            &[(&devices[0], true), (&devices[1], false)],
+            switch_bot.api_stats(),
+        );
+        assert!(text.contains("# TYPE switchbot_device_up gauge\n"));
+        assert!(text.contains(
+            r#"switchbot_device_up{device_id="device1",device_name="Device 1",device_type="test"} 1"#
+        ));
+        assert!(text.contains(r#"switchbot_device_up{device_id="device2","#));
+        assert!(text.contains("# TYPE switchbot_api_requests_total counter\n"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(Exporter::escape(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+}
@@ -17,5 +17,13 @@ mod args;
 pub(crate) use args::Args;
 mod cli;
 pub use cli::Cli;
+mod credentials;
+pub(crate) use credentials::*;
+mod custom_buttons;
+pub(crate) use custom_buttons::CustomButtons;
+mod exporter;
+pub(crate) use exporter::Exporter;
+mod mqtt_bridge;
+pub(crate) use mqtt_bridge::MqttBridge;
 mod user_input;
 pub(crate) use user_input::UserInput;
@@ -1,33 +1,72 @@
 use std::io::Write;
 
+use switchbot_api::SwitchBot;
 use switchbot_cli::Cli;
 
 #[tokio::main]
 async fn main() {
-    init_logger();
-
     let mut cli = Cli::new_from_args();
+    init_logger(cli.is_dump_http());
+
     if let Err(error) = cli.run().await {
         log::error!("{error}");
+        std::process::exit(1);
     }
 }
 
-fn init_logger() {
+fn init_logger(dump_http: bool) {
     // If `RUST_LOG` is set, initialize the `env_logger` in its default config.
-    if std::env::var("RUST_LOG").is_ok() {
-        env_logger::init();
-        return;
+    let logger = if std::env::var("RUST_LOG").is_ok() {
+        env_logger::Logger::from_default_env()
+    } else {
+        env_logger::Builder::new()
+            .filter_level(log::LevelFilter::Info)
+            .format(|buf, record| match record.level() {
+                log::Level::Info => writeln!(buf, "{}", record.args()),
+                _ => {
+                    let style = buf.default_level_style(record.level());
+                    writeln!(buf, "{style}{}{style:#}: {}", record.level(), record.args())
+                }
+            })
+            .target(env_logger::Target::Stdout)
+            .build()
+    };
+    let max_level = if dump_http {
+        log::LevelFilter::Trace
+    } else {
+        logger.filter()
+    };
+    log::set_boxed_logger(Box::new(Logger { logger, dump_http })).unwrap();
+    log::set_max_level(max_level);
+}
+
+/// The `env_logger`, except that the [`SwitchBot::HTTP_LOG_TARGET`] is
+/// printed to stderr as is if the `--dump-http` option is specified.
+struct Logger {
+    logger: env_logger::Logger,
+    dump_http: bool,
+}
+
+impl Logger {
+    fn is_dump_http(&self, metadata: &log::Metadata) -> bool {
+        self.dump_http && metadata.target() == SwitchBot::HTTP_LOG_TARGET
+    }
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.is_dump_http(metadata) || self.logger.enabled(metadata)
     }
 
-    env_logger::Builder::new()
-        .filter_level(log::LevelFilter::Info)
-        .format(|buf, record| match record.level() {
-            log::Level::Info => writeln!(buf, "{}", record.args()),
-            _ => {
-                let style = buf.default_level_style(record.level());
-                writeln!(buf, "{style}{}{style:#}: {}", record.level(), record.args())
-            }
-        })
-        .target(env_logger::Target::Stdout)
-        .init();
+    fn log(&self, record: &log::Record) {
+        if self.is_dump_http(record.metadata()) {
+            eprintln!("{}", record.args());
+            return;
+        }
+        self.logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
 }
@@ -14,18 +14,27 @@ from typing import Any
 class Section(Enum):
     NONE = auto()
     DEVICE_LIST_INFO = auto()
+    DEVICE_STATUS = auto()
     CONTROL_COMMANDS = auto()
 
 
+# The version of the JSON schema. Increment when `Help` in Rust needs changes.
+SCHEMA_VERSION = 1
+
+
 @dataclass
 class HelpData:
+    version: int
     commands: dict[str, list[dict[str, Any]]]
     commands_ir: dict[str, list[dict[str, Any]]]
+    status: dict[str, list[dict[str, Any]]]
     device_type_aliases: dict[str, list[str]]
 
     def __init__(self, devices_dir: str | Path) -> None:
+        self.version = SCHEMA_VERSION
         self.commands = {}
         self.commands_ir = {}
+        self.status = {}
         self.device_type_aliases = {}
 
         devices_path = Path(devices_dir)
@@ -45,6 +54,10 @@ class HelpData:
             for alias in parsed.aliases:
                 self.add_device_type_alias(alias, parsed.device_name)
 
+            # Add status
+            if parsed.status:
+                self.status.setdefault(parsed.device_name, []).extend(parsed.status)
+
             # Add commands
             for dev_type, cmd in parsed.commands:
                 self.commands.setdefault(dev_type, []).append(cmd)
@@ -70,22 +83,8 @@ class HelpData:
         # are under "Standing Circulator Fan".
         self.add_device_type_alias("Standing Fan", "Standing Circulator Fan")
 
-        # 2. commands_ir finalization (Others and All home appliance types except Others)
-        other_key = "Others"
-        if other_key in self.commands_ir:
-            others = self.commands_ir.pop(other_key)
-            # Append others to all other IR device types
-            for k, v in self.commands_ir.items():
-                # Avoid modifying shared dict references if they were shared
-                v.extend([json.loads(json.dumps(x)) for x in others])
-
-        all_key = "All home appliance types except Others"
-        if all_key in self.commands_ir:
-            all_app = self.commands_ir.pop(all_key)
-            # Prepend to all other IR device types
-            for k, v in self.commands_ir.items():
-                copied = [json.loads(json.dumps(x)) for x in all_app]
-                self.commands_ir[k] = copied + v
+        # "All home appliance types except Others" and "Others" in `commands_ir`
+        # are kept as they are. `Help` merges them into each IR device type.
 
     def add_device_type_alias(self, alias: str, target: str) -> None:
         if alias not in self.device_type_aliases:
@@ -135,6 +134,7 @@ class ParsedMarkdown:
     aliases: list[str]
     commands: list[tuple[str, dict[str, Any]]]
     commands_ir: list[tuple[str, dict[str, Any]]]
+    status: list[dict[str, Any]]
     _section: Section
     _in_command_table: bool
     _current_device_type: str
@@ -144,6 +144,7 @@ class ParsedMarkdown:
         self.aliases = []
         self.commands = []
         self.commands_ir = []
+        self.status = []
         self._section = Section.NONE
         self._in_command_table = False
         self._current_device_type = ""
@@ -158,6 +159,9 @@ class ParsedMarkdown:
                 self._section = Section.DEVICE_LIST_INFO
                 self._in_command_table = False
                 continue
+            elif stripped in ("## Device Status", "## Get Device Status", "## Status"):
+                self._section = Section.DEVICE_STATUS
+                continue
             elif stripped == "## Control Commands":
                 self._section = Section.CONTROL_COMMANDS
                 self._in_command_table = False
@@ -171,6 +175,8 @@ class ParsedMarkdown:
 
             if self._section == Section.DEVICE_LIST_INFO:
                 self._parse_device_list_info_line(stripped)
+            elif self._section == Section.DEVICE_STATUS:
+                self._parse_device_status_line(stripped)
             elif self._section == Section.CONTROL_COMMANDS:
                 self._parse_control_commands_line(stripped, file_path.name)
 
@@ -193,6 +199,32 @@ class ParsedMarkdown:
             if m and m != self.device_name:
                 self.aliases.append(m)
 
+    # Keys in the status response that are not the status of the device.
+    _STATUS_KEYS_TO_SKIP = ("Key", "deviceId", "deviceType", "hubDeviceId")
+
+    def _parse_device_status_line(self, stripped: str) -> None:
+        if not (stripped.startswith("|") and stripped.endswith("|")):
+            return
+
+        columns = [c.strip() for c in stripped.strip("|").split("|")]
+        if len(columns) < 3:
+            return
+        key = columns[0].strip("`")
+        if not key or key.startswith("-") or key in self._STATUS_KEYS_TO_SKIP:
+            return
+
+        description = columns[2]
+        unit, value_range = parse_unit_and_range(description)
+        self.status.append(
+            {
+                "key": key,
+                "type": columns[1],
+                "unit": unit,
+                "range": value_range,
+                "description": description,
+            }
+        )
+
     def _parse_control_commands_line(self, stripped: str, file_name: str) -> None:
         if not (stripped.startswith("|") and stripped.endswith("|")):
             self._in_command_table = False
@@ -232,6 +264,37 @@ class ParsedMarkdown:
             self.commands.append((self._current_device_type, cmd_help))
 
 
+_UNITS = (
+    (r"\bcelsius\b", "°C"),
+    (r"\bfahrenheit\b", "°F"),
+    (r"\bppm\b", "ppm"),
+    (r"\bkelvin\b", "K"),
+    (r"\bwatts?\b", "W"),
+    (r"\bvolts?\b", "V"),
+    (r"\bmA\b", "mA"),
+    (r"\bamps?\b", "A"),
+    (r"\bminutes?\b", "min"),
+    (r"\bseconds?\b", "s"),
+    (r"\bpercent(age)?\b|%|\bbattery level\b", "%"),
+)
+
+
+def parse_unit_and_range(description: str) -> tuple[str, str]:
+    """Extract the unit and the range of values from the description text."""
+    unit = ""
+    for pattern, name in _UNITS:
+        if re.search(pattern, description, re.IGNORECASE):
+            unit = name
+            break
+    value_range = ""
+    match = re.search(
+        r"(-?\d+(?:\.\d+)?)\s*(?:-|~|to)\s*(-?\d+(?:\.\d+)?)", description
+    )
+    if match:
+        value_range = f"{match.group(1)}-{match.group(2)}"
+    return (unit, value_range)
+
+
 def main() -> None:
     parser = argparse.ArgumentParser(
         description="Generate SwitchBotAPI help JSON from Markdown files."
@@ -2,13 +2,20 @@ import tempfile
 import unittest
 from pathlib import Path
 
-from generate import HelpData, ParsedMarkdown, Section
+from generate import (
+    SCHEMA_VERSION,
+    HelpData,
+    ParsedMarkdown,
+    Section,
+    parse_unit_and_range,
+)
 
 
 class TestGenerate(unittest.TestCase):
     def test_section_enum(self):
         self.assertEqual(Section.NONE.name, "NONE")
         self.assertEqual(Section.DEVICE_LIST_INFO.name, "DEVICE_LIST_INFO")
+        self.assertEqual(Section.DEVICE_STATUS.name, "DEVICE_STATUS")
         self.assertEqual(Section.CONTROL_COMMANDS.name, "CONTROL_COMMANDS")
 
     def test_parse_markdown_file_physical(self):
@@ -93,6 +100,64 @@ Here is some text before the table that should not break parsing.
                 ),
             )
 
+    def test_parse_markdown_file_status(self):
+        content = """# Test Meter
+
+## Device Status
+
+| Key         | Value Type | Description |
+| ----------- | ---------- | ----------- |
+| deviceId    | String     | device ID |
+| temperature | Float      | temperature in celsius |
+| battery     | Integer    | the current battery level, 0-100 |
+| version     | String     | the current firmware version, e.g. V4.2 |
+"""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            file_path = Path(tmpdir) / "test-meter.md"
+            with open(file_path, "w", encoding="utf-8") as f:
+                f.write(content)
+
+            parsed = ParsedMarkdown(file_path)
+
+            self.assertEqual(parsed.device_name, "Test Meter")
+            self.assertEqual(
+                parsed.status,
+                [
+                    {
+                        "key": "temperature",
+                        "type": "Float",
+                        "unit": "°C",
+                        "range": "",
+                        "description": "temperature in celsius",
+                    },
+                    {
+                        "key": "battery",
+                        "type": "Integer",
+                        "unit": "%",
+                        "range": "0-100",
+                        "description": "the current battery level, 0-100",
+                    },
+                    {
+                        "key": "version",
+                        "type": "String",
+                        "unit": "",
+                        "range": "",
+                        "description": "the current firmware version, e.g. V4.2",
+                    },
+                ],
+            )
+
+            data = HelpData(tmpdir)
+            self.assertEqual(len(data.status["Test Meter"]), 3)
+
+    def test_parse_unit_and_range(self):
+        self.assertEqual(parse_unit_and_range("temperature in celsius"), ("°C", ""))
+        self.assertEqual(parse_unit_and_range("humidity percentage"), ("%", ""))
+        self.assertEqual(parse_unit_and_range("level, 1~20"), ("", "1-20"))
+        self.assertEqual(
+            parse_unit_and_range("measured in Watts, 0 to 3840"), ("W", "0-3840")
+        )
+
     def test_parse_markdown_file_ir(self):
         content = """# Virtual infrared remote devices
 
@@ -172,6 +237,7 @@ Here is some text before the table that should not break parsing.
                 f.write(fan_content)
 
             data = HelpData(tmpdir)
+            self.assertEqual(data.version, SCHEMA_VERSION)
 
             # Check Standing Fan alias
             self.assertIn("Standing Fan", data.device_type_aliases)
@@ -179,15 +245,19 @@ Here is some text before the table that should not break parsing.
                 data.device_type_aliases["Standing Fan"], ["Standing Circulator Fan"]
             )
 
-            # Check commands_ir finalization
+            # Check commands_ir keeps the shared keys
             self.assertIn("TV", data.commands_ir)
             tv_cmds = data.commands_ir["TV"]
-
-            self.assertEqual(len(tv_cmds), 4)
-            self.assertEqual(tv_cmds[0]["command"]["command"], "turnOn")
-            self.assertEqual(tv_cmds[1]["command"]["command"], "btn1")
-            self.assertEqual(tv_cmds[2]["command"]["command"], "setCh")
-            self.assertEqual(tv_cmds[3]["command"]["command"], "btn1")
+            self.assertEqual(len(tv_cmds), 1)
+            self.assertEqual(tv_cmds[0]["command"]["command"], "setCh")
+
+            all_key = "All home appliance types except Others"
+            all_cmds = data.commands_ir[all_key]
+            self.assertEqual(len(all_cmds), 1)
+            self.assertEqual(all_cmds[0]["command"]["command"], "turnOn")
+            others_cmds = data.commands_ir["Others"]
+            self.assertEqual(len(others_cmds), 1)
+            self.assertEqual(others_cmds[0]["command"]["command"], "btn1")
 
 
 if __name__ == "__main__":